@@ -0,0 +1,204 @@
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::collect_files;
+use crate::commands::messages::{ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::{ColorType, DynamicImage, ImageFormat, ImageReader};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Hash the decoded pixels of images so that re-encoding a file does not change its checksum.
+///
+/// The checksum covers the colour type, dimensions and pixel data of the image.
+/// Output is formatted as '<algorithm>:<checksum>  <path>' per line, with '+normalized'
+/// after the algorithm when --normalize is used. This can be saved as a manifest and
+/// checked later with --verify using the same --algorithm and --normalize options.
+#[derive(Debug, Clone, Parser)]
+pub struct ChecksumCommand {
+    /// Paths on disk to images or directories of images that should be hashed.
+    #[arg(required_unless_present = "manifest_path")]
+    pub paths: Vec<PathBuf>,
+
+    /// Hash the images in directories recursively.
+    #[arg(short = 'r', long = "recursive", default_value_t = false)]
+    pub recursive: bool,
+
+    /// The hashing algorithm to use.
+    #[arg(long = "algorithm", default_value = "sha256")]
+    pub algorithm: ChecksumAlgorithm,
+
+    /// Convert pixels to 8-bit RGBA before hashing, so images with the same
+    /// appearance but different colour types produce the same checksum.
+    #[arg(long = "normalize", default_value_t = false)]
+    pub normalize: bool,
+
+    /// A path on disk to a manifest whose checksums should be verified.
+    #[arg(long = "verify", conflicts_with_all = ["paths", "recursive"])]
+    pub manifest_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    Blake3,
+}
+
+/// Written before the pixel data so the checksum format can be changed in the future.
+const CHECKSUM_DOMAIN: &[u8] = b"imgutils-pixel-checksum-v1";
+
+impl ChecksumAlgorithm {
+    fn checksum(self, image: &DynamicImage) -> String {
+        match self {
+            Self::Sha256 => {
+                let mut hasher = Sha256::new();
+                write_pixels(image, |bytes| hasher.update(bytes));
+                to_hex(&hasher.finalize())
+            }
+            Self::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                write_pixels(image, |bytes| {
+                    hasher.update(bytes);
+                });
+                to_hex(hasher.finalize().as_bytes())
+            }
+        }
+    }
+}
+
+/// Feed the header and pixel data of an image to a hasher, with multi-byte samples
+/// in little-endian order so checksums are the same on every platform.
+fn write_pixels(image: &DynamicImage, mut update: impl FnMut(&[u8])) {
+    update(CHECKSUM_DOMAIN);
+    update(&color_tag(image.color()));
+    update(&image.width().to_le_bytes());
+    update(&image.height().to_le_bytes());
+    if let Some(samples) = image.as_flat_samples_u8() {
+        update(samples.samples);
+    } else if let Some(samples) = image.as_flat_samples_u16() {
+        for chunk in samples.samples.chunks(4096) {
+            let bytes: Vec<u8> = chunk.iter().flat_map(|v| v.to_le_bytes()).collect();
+            update(&bytes);
+        }
+    } else if let Some(samples) = image.as_flat_samples_f32() {
+        for chunk in samples.samples.chunks(4096) {
+            let bytes: Vec<u8> = chunk.iter().flat_map(|v| v.to_le_bytes()).collect();
+            update(&bytes);
+        }
+    }
+}
+
+/// A fixed identifier for a colour type followed by its bits per channel, so checksums
+/// don't depend on how the `image` crate names its colour types.
+fn color_tag(color: ColorType) -> [u8; 2] {
+    let tag = match color {
+        ColorType::L8 => 0,
+        ColorType::La8 => 1,
+        ColorType::Rgb8 => 2,
+        ColorType::Rgba8 => 3,
+        ColorType::L16 => 4,
+        ColorType::La16 => 5,
+        ColorType::Rgb16 => 6,
+        ColorType::Rgba16 => 7,
+        ColorType::Rgb32F => 8,
+        ColorType::Rgba32F => 9,
+        _ => u8::MAX,
+    };
+    let bits = color.bits_per_pixel() / u16::from(color.channel_count());
+    [tag, bits as u8]
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl ChecksumCommand {
+    /// The prefix written before each checksum, recording the options that produced it.
+    fn label(&self) -> String {
+        let algorithm = self
+            .algorithm
+            .to_possible_value()
+            .expect("no algorithms are skipped");
+        if self.normalize {
+            format!("{}+normalized", algorithm.get_name())
+        } else {
+            algorithm.get_name().to_string()
+        }
+    }
+
+    fn checksum_file(&self, path: &Path) -> Result<String> {
+        let image = ImageReader::open(path)
+            .context(ERROR_IMGREAD_CTX)?
+            .with_guessed_format()
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        let image = if self.normalize {
+            DynamicImage::ImageRgba8(image.into_rgba8())
+        } else {
+            image
+        };
+        Ok(self.algorithm.checksum(&image))
+    }
+}
+
+impl ExecutableCommand for ChecksumCommand {
+    fn run(self) -> Result<()> {
+        let Some(manifest_path) = &self.manifest_path else {
+            // Files found inside directories are skipped unless they look like images,
+            // while files passed explicitly are always checksummed.
+            let files = collect_files(&self.paths, self.recursive)?
+                .into_iter()
+                .filter(|path| self.paths.contains(path) || ImageFormat::from_path(path).is_ok());
+            for path in files {
+                let checksum = self
+                    .checksum_file(&path)
+                    .with_context(|| format!("failed to checksum {}", path.display()))?;
+                println!("{}:{}  {}", self.label(), checksum, path.display());
+            }
+            return Ok(());
+        };
+
+        let manifest = fs::read_to_string(manifest_path).context("failed to read manifest")?;
+        let mut failures = 0;
+        for (line_number, line) in manifest.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Some((checksum, path)) = line.split_once("  ") else {
+                bail!("invalid manifest entry on line {}", line_number + 1);
+            };
+            let Some((label, expected)) = checksum.split_once(':') else {
+                bail!("invalid manifest entry on line {}", line_number + 1);
+            };
+            if label != self.label() {
+                bail!(
+                    "line {} of the manifest was created with '{}' but is being verified with '{}', \
+                     pass the same --algorithm and --normalize options that created the manifest",
+                    line_number + 1,
+                    label,
+                    self.label()
+                );
+            }
+            match self.checksum_file(Path::new(path)) {
+                Ok(actual) if actual.eq_ignore_ascii_case(expected.trim()) => {
+                    println!("{}: OK", path)
+                }
+                Ok(_) => {
+                    failures += 1;
+                    println!("{}: FAILED", path);
+                }
+                Err(err) => {
+                    failures += 1;
+                    println!("{}: FAILED ({:#})", path, err);
+                }
+            }
+        }
+
+        if failures > 0 {
+            bail!("{} image(s) did not match their checksum", failures);
+        }
+
+        Ok(())
+    }
+}
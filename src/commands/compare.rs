@@ -0,0 +1,327 @@
+use crate::commands::ExecutableCommand;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::{DynamicImage, GenericImageView, ImageFormat, ImageReader, Rgb, RgbImage};
+use std::path::{Path, PathBuf};
+
+/// Compare two images of the same dimensions and report how much they differ.
+///
+/// MAE and RMSE are measured on a 0.0 to 1.0 scale, and SSIM/DSSIM are measured
+/// on luminance. When --threshold is given the command fails if the chosen metric
+/// is worse than the threshold, which makes it suitable for gating CI.
+#[derive(Debug, Clone, Parser)]
+pub struct CompareCommand {
+    /// A path on disk to the first image.
+    #[arg(short = 'a', long = "first")]
+    pub a_path: PathBuf,
+
+    /// A path on disk to the second image.
+    #[arg(short = 'b', long = "second")]
+    pub b_path: PathBuf,
+
+    /// A path on disk to where an image highlighting the differences should be placed.
+    #[arg(long = "diff")]
+    pub diff_path: Option<PathBuf>,
+
+    /// The metric checked against --threshold.
+    #[arg(long = "metric", default_value = "rmse")]
+    pub metric: CompareMetric,
+
+    /// The worst acceptable value of the chosen metric. For MAE, RMSE and DSSIM the
+    /// comparison fails above this value, and for PSNR and SSIM it fails below it.
+    #[arg(long = "threshold")]
+    pub threshold: Option<f64>,
+
+    /// Overwrite any existing file at the diff output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum CompareMetric {
+    /// Mean absolute error.
+    Mae,
+
+    /// Root mean squared error.
+    Rmse,
+
+    /// Peak signal-to-noise ratio in decibels.
+    Psnr,
+
+    /// Structural similarity index.
+    Ssim,
+
+    /// Structural dissimilarity, (1 - SSIM) / 2.
+    Dssim,
+}
+
+impl CompareMetric {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Mae => "MAE",
+            Self::Rmse => "RMSE",
+            Self::Psnr => "PSNR",
+            Self::Ssim => "SSIM",
+            Self::Dssim => "DSSIM",
+        }
+    }
+}
+
+/// The standard deviation and radius of the Gaussian window used for SSIM.
+const SSIM_SIGMA: f64 = 1.5;
+const SSIM_RADIUS: usize = 5;
+const SSIM_C1: f64 = 0.01 * 0.01;
+const SSIM_C2: f64 = 0.03 * 0.03;
+
+/// How much per-pixel differences are amplified in the diff image so small changes stay visible.
+const DIFF_AMPLIFICATION: f64 = 4.0;
+const DIFF_BACKGROUND_DIM: f64 = 0.3;
+
+impl ExecutableCommand for CompareCommand {
+    fn run(self) -> Result<()> {
+        if let Some(diff_path) = &self.diff_path {
+            if diff_path.exists() && !self.overwrite {
+                bail!(OUTPUT_ALREADY_EXISTS);
+            }
+        }
+
+        let a = load_image(&self.a_path)?;
+        let b = load_image(&self.b_path)?;
+        if a.dimensions() != b.dimensions() {
+            bail!(
+                "images must have the same dimensions to be compared ({}x{} vs {}x{})",
+                a.width(),
+                a.height(),
+                b.width(),
+                b.height()
+            );
+        }
+
+        let (mae, rmse, differences) = errors(&a, &b);
+        let psnr = psnr(rmse);
+        let ssim = ssim(&a, &b);
+        let dssim = (1.0 - ssim) / 2.0;
+
+        println!("MAE: {:.6}", mae);
+        println!("RMSE: {:.6}", rmse);
+        println!("PSNR: {:.2} dB", psnr);
+        println!("SSIM: {:.6}", ssim);
+        println!("DSSIM: {:.6}", dssim);
+
+        if let Some(diff_path) = &self.diff_path {
+            let output_format =
+                ImageFormat::from_path(diff_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+            let base = a.to_luma32f();
+            RgbImage::from_fn(a.width(), a.height(), |x, y| {
+                let background =
+                    base.get_pixel(x, y)[0].clamp(0.0, 1.0) as f64 * DIFF_BACKGROUND_DIM * 255.0;
+                let highlight =
+                    (differences[(y * a.width() + x) as usize] * DIFF_AMPLIFICATION).min(1.0);
+                Rgb([
+                    background + (255.0 - background) * highlight,
+                    background * (1.0 - highlight),
+                    background * (1.0 - highlight),
+                ]
+                .map(|v| v.round() as u8))
+            })
+            .save_with_format(diff_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+        }
+
+        if let Some(threshold) = self.threshold {
+            // PSNR and SSIM measure similarity, so they fail when below the threshold.
+            let (value, higher_is_better) = match self.metric {
+                CompareMetric::Mae => (mae, false),
+                CompareMetric::Rmse => (rmse, false),
+                CompareMetric::Psnr => (psnr, true),
+                CompareMetric::Ssim => (ssim, true),
+                CompareMetric::Dssim => (dssim, false),
+            };
+            let (failed, comparison) = if higher_is_better {
+                (value < threshold, "is below")
+            } else {
+                (value > threshold, "exceeds")
+            };
+            if failed {
+                bail!(
+                    "{} of {:.6} {} the threshold of {}",
+                    self.metric.name(),
+                    value,
+                    comparison,
+                    threshold
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn load_image(path: &Path) -> Result<DynamicImage> {
+    if !path.exists() {
+        bail!(INPUT_FILE_DOES_NOT_EXIST);
+    }
+    if !path.is_file() {
+        bail!(INPUT_IS_NOT_FILE);
+    }
+    ImageReader::open(path)
+        .context(ERROR_IMGREAD_CTX)?
+        .decode()
+        .context(ERROR_IMGDECODE_CTX)
+}
+
+/// The mean absolute and root mean squared errors between two images, along with the
+/// largest channel difference of each pixel.
+fn errors(a: &DynamicImage, b: &DynamicImage) -> (f64, f64, Vec<f64>) {
+    let channels = if a.color().has_alpha() || b.color().has_alpha() {
+        4
+    } else {
+        3
+    };
+    let (a_pixels, b_pixels) = (a.to_rgba32f(), b.to_rgba32f());
+    let (mut absolute, mut squared) = (0.0f64, 0.0f64);
+    let mut differences = Vec::with_capacity((a.width() * a.height()) as usize);
+    for (pa, pb) in a_pixels.pixels().zip(b_pixels.pixels()) {
+        let mut largest = 0.0f64;
+        for c in 0..channels {
+            let difference = (pa[c].clamp(0.0, 1.0) - pb[c].clamp(0.0, 1.0)).abs() as f64;
+            absolute += difference;
+            squared += difference * difference;
+            largest = largest.max(difference);
+        }
+        differences.push(largest);
+    }
+    let samples = (differences.len() * channels) as f64;
+    let mae = absolute / samples;
+    let rmse = (squared / samples).sqrt();
+    (mae, rmse, differences)
+}
+
+/// The peak signal-to-noise ratio in decibels of a root mean squared error.
+fn psnr(rmse: f64) -> f64 {
+    if rmse == 0.0 {
+        f64::INFINITY
+    } else {
+        -20.0 * rmse.log10()
+    }
+}
+
+/// Compute the mean structural similarity of the luminance of two images
+/// using a Gaussian-weighted window (Wang et al. 2004).
+fn ssim(a: &DynamicImage, b: &DynamicImage) -> f64 {
+    let (width, height) = (a.width() as usize, a.height() as usize);
+    let x: Vec<f64> = a
+        .to_luma32f()
+        .pixels()
+        .map(|p| p[0].clamp(0.0, 1.0) as f64)
+        .collect();
+    let y: Vec<f64> = b
+        .to_luma32f()
+        .pixels()
+        .map(|p| p[0].clamp(0.0, 1.0) as f64)
+        .collect();
+
+    let kernel: Vec<f64> = {
+        let weights: Vec<f64> = (0..=2 * SSIM_RADIUS)
+            .map(|i| {
+                let d = i as f64 - SSIM_RADIUS as f64;
+                (-(d * d) / (2.0 * SSIM_SIGMA * SSIM_SIGMA)).exp()
+            })
+            .collect();
+        let sum: f64 = weights.iter().sum();
+        weights.into_iter().map(|w| w / sum).collect()
+    };
+    let blur = |values: &[f64]| gaussian_blur(values, width, height, &kernel);
+
+    let xx: Vec<f64> = x.iter().map(|v| v * v).collect();
+    let yy: Vec<f64> = y.iter().map(|v| v * v).collect();
+    let xy: Vec<f64> = x.iter().zip(&y).map(|(a, b)| a * b).collect();
+    let (mu_x, mu_y) = (blur(&x), blur(&y));
+    let (blur_xx, blur_yy, blur_xy) = (blur(&xx), blur(&yy), blur(&xy));
+
+    let total: f64 = (0..width * height)
+        .map(|i| {
+            let sigma_x = blur_xx[i] - mu_x[i] * mu_x[i];
+            let sigma_y = blur_yy[i] - mu_y[i] * mu_y[i];
+            let sigma_xy = blur_xy[i] - mu_x[i] * mu_y[i];
+            ((2.0 * mu_x[i] * mu_y[i] + SSIM_C1) * (2.0 * sigma_xy + SSIM_C2))
+                / ((mu_x[i] * mu_x[i] + mu_y[i] * mu_y[i] + SSIM_C1)
+                    * (sigma_x + sigma_y + SSIM_C2))
+        })
+        .sum();
+    total / (width * height) as f64
+}
+
+/// Separable convolution with clamped edges.
+fn gaussian_blur(values: &[f64], width: usize, height: usize, kernel: &[f64]) -> Vec<f64> {
+    let radius = kernel.len() / 2;
+    let clamp = |v: isize, max: usize| v.clamp(0, max as isize - 1) as usize;
+    let mut horizontal = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            horizontal[y * width + x] = kernel
+                .iter()
+                .enumerate()
+                .map(|(k, w)| {
+                    w * values[y * width + clamp(x as isize + k as isize - radius as isize, width)]
+                })
+                .sum();
+        }
+    }
+    let mut output = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            output[y * width + x] = kernel
+                .iter()
+                .enumerate()
+                .map(|(k, w)| {
+                    w * horizontal
+                        [clamp(y as isize + k as isize - radius as isize, height) * width + x]
+                })
+                .sum();
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    fn gradient() -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(32, 24, |x, y| {
+            Rgba([(x * 8) as u8, (y * 10) as u8, ((x * y) % 256) as u8, 255])
+        }))
+    }
+
+    #[test]
+    fn identical_images_have_no_error() {
+        let (mae, rmse, differences) = errors(&gradient(), &gradient());
+        assert_eq!(mae, 0.0);
+        assert_eq!(rmse, 0.0);
+        assert!(differences.iter().all(|d| *d == 0.0));
+        assert_eq!(psnr(rmse), f64::INFINITY);
+    }
+
+    #[test]
+    fn identical_images_have_ssim_of_one() {
+        assert!((ssim(&gradient(), &gradient()) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn different_images_are_less_similar() {
+        let inverted = {
+            let mut image = gradient();
+            image.invert();
+            image
+        };
+        let (_, rmse, _) = errors(&gradient(), &inverted);
+        assert!(psnr(rmse).is_finite());
+        assert!(ssim(&gradient(), &inverted) < 0.5);
+    }
+}
@@ -0,0 +1,191 @@
+use super::hash::{PerceptualHash, hamming_distance, to_hex};
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::collect_files;
+use anyhow::{Context, Result};
+use clap::{Parser, ValueEnum};
+use image::{ImageFormat, ImageReader};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Find groups of near-duplicate images using perceptual hashes.
+#[derive(Debug, Clone, Parser)]
+pub struct DupesCommand {
+    /// Paths on disk to images or directories of images that should be scanned.
+    #[arg(required = true)]
+    pub paths: Vec<PathBuf>,
+
+    /// Scan directories recursively.
+    #[arg(short = 'r', long = "recursive", default_value_t = false)]
+    pub recursive: bool,
+
+    /// The hashing algorithm used to compare images.
+    #[arg(long = "algorithm", default_value = "phash")]
+    pub algorithm: PerceptualHash,
+
+    /// The largest number of differing hash bits (0 to 64) for two images to count as duplicates.
+    #[arg(long = "distance", default_value_t = 8, value_parser = clap::value_parser!(u32).range(0..=64))]
+    pub distance: u32,
+
+    /// Suggest which image of each group should be kept.
+    #[arg(long = "keep")]
+    pub keep: Option<KeepStrategy>,
+
+    /// How the groups should be output.
+    #[arg(long = "format", default_value = "text")]
+    pub format: DupesOutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum KeepStrategy {
+    /// Keep the image with the most pixels, then the largest file.
+    Largest,
+
+    /// Keep the most recently modified image.
+    Newest,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum DupesOutputFormat {
+    /// Print each group of duplicates in a pretty format.
+    Text,
+
+    /// Print a JSON document.
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ScannedImage {
+    path: PathBuf,
+    hash: String,
+    width: u32,
+    height: u32,
+    bytes: u64,
+    /// Seconds since the Unix epoch.
+    modified: u64,
+    #[serde(skip)]
+    hash_value: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct DuplicateGroup {
+    images: Vec<ScannedImage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep: Option<PathBuf>,
+}
+
+impl ExecutableCommand for DupesCommand {
+    fn run(self) -> Result<()> {
+        let files: Vec<PathBuf> = collect_files(&self.paths, self.recursive)?
+            .into_iter()
+            .filter(|path| ImageFormat::from_path(path).is_ok())
+            .collect();
+
+        let images: Vec<ScannedImage> = files
+            .into_par_iter()
+            .filter_map(|path| match scan_image(&path, self.algorithm) {
+                Ok(image) => Some(image),
+                Err(err) => {
+                    eprintln!("Skipping {}: {:#}", path.display(), err);
+                    None
+                }
+            })
+            .collect();
+
+        // Union every pair of images within the distance so groups are transitive.
+        let mut parents: Vec<usize> = (0..images.len()).collect();
+        fn root(parents: &mut [usize], mut i: usize) -> usize {
+            while parents[i] != i {
+                parents[i] = parents[parents[i]];
+                i = parents[i];
+            }
+            i
+        }
+        for a in 0..images.len() {
+            for b in a + 1..images.len() {
+                if hamming_distance(images[a].hash_value, images[b].hash_value) <= self.distance {
+                    let (root_a, root_b) = (root(&mut parents, a), root(&mut parents, b));
+                    parents[root_b] = root_a;
+                }
+            }
+        }
+
+        let mut groups: Vec<Vec<ScannedImage>> = vec![Vec::new(); images.len()];
+        for (i, image) in images.into_iter().enumerate() {
+            let group = root(&mut parents, i);
+            groups[group].push(image);
+        }
+        let groups: Vec<DuplicateGroup> = groups
+            .into_iter()
+            .filter(|group| group.len() > 1)
+            .map(|images| {
+                let keep = self.keep.and_then(|strategy| {
+                    images
+                        .iter()
+                        .max_by_key(|image| match strategy {
+                            KeepStrategy::Largest => {
+                                (image.width as u64 * image.height as u64, image.bytes)
+                            }
+                            KeepStrategy::Newest => (image.modified, 0),
+                        })
+                        .map(|image| image.path.clone())
+                });
+                DuplicateGroup { images, keep }
+            })
+            .collect();
+
+        match self.format {
+            DupesOutputFormat::Text => {
+                if groups.is_empty() {
+                    println!("No duplicate images were found");
+                }
+                for (i, group) in groups.iter().enumerate() {
+                    println!("Group {} ({} images)", i + 1, group.images.len());
+                    for image in &group.images {
+                        let suffix = if group.keep.as_ref() == Some(&image.path) {
+                            " [keep]"
+                        } else {
+                            ""
+                        };
+                        println!(
+                            "  * {} ({}x{}, {} bytes, hash {}){}",
+                            image.path.display(),
+                            image.width,
+                            image.height,
+                            image.bytes,
+                            image.hash,
+                            suffix
+                        );
+                    }
+                }
+            }
+            DupesOutputFormat::Json => println!("{}", serde_json::to_string_pretty(&groups)?),
+        }
+
+        Ok(())
+    }
+}
+
+fn scan_image(path: &Path, algorithm: PerceptualHash) -> Result<ScannedImage> {
+    let metadata = path.metadata()?;
+    let image = ImageReader::open(path)?
+        .with_guessed_format()?
+        .decode()
+        .context("failed to decode image")?;
+    let hash = algorithm.hash(&image);
+    Ok(ScannedImage {
+        path: path.to_path_buf(),
+        hash: to_hex(hash),
+        width: image.width(),
+        height: image.height(),
+        bytes: metadata.len(),
+        modified: metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default()
+            .as_secs(),
+        hash_value: hash,
+    })
+}
@@ -0,0 +1,258 @@
+use crate::commands::ExecutableCommand;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE,
+};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage, ImageReader};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::path::PathBuf;
+
+/// Print perceptual hashes of images, which stay similar when images are resized or re-encoded.
+#[derive(Debug, Clone, Parser)]
+pub struct HashCommand {
+    /// Paths on disk to the images that should be hashed.
+    #[arg(short = 'i', long = "input", required = true, num_args = 1..)]
+    pub input_paths: Vec<PathBuf>,
+
+    /// The hashing algorithms to use. Defaults to all of them.
+    #[arg(long = "algorithm", value_delimiter = ',')]
+    pub algorithms: Vec<PerceptualHash>,
+
+    /// How the hashes should be output.
+    #[arg(long = "format", default_value = "text")]
+    pub format: HashOutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum PerceptualHash {
+    /// Average hash: whether each pixel of an 8x8 thumbnail is brighter than the mean.
+    Ahash,
+
+    /// Difference hash: whether each pixel of a 9x8 thumbnail is brighter than its neighbour.
+    Dhash,
+
+    /// Perceptual hash: the signs of the low frequencies of a discrete cosine transform.
+    Phash,
+
+    /// Blockhash: whether each block of an 8x8 grid over the whole image is brighter than the
+    /// median of its band, as described at blockhash.io. Transparent pixels count as white.
+    Blockhash,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum HashOutputFormat {
+    /// Print the hashes of each image in a pretty format.
+    Text,
+
+    /// Print a JSON document.
+    Json,
+}
+
+#[derive(Debug, Serialize)]
+struct HashReport {
+    path: PathBuf,
+    hashes: BTreeMap<String, String>,
+}
+
+/// The size images are shrunk to before the DCT of the perceptual hash is taken.
+const PHASH_SIZE: usize = 32;
+const PHASH_FREQUENCIES: usize = 8;
+const BLOCKHASH_GRID: usize = 8;
+
+impl PerceptualHash {
+    pub const ALL: [Self; 4] = [Self::Ahash, Self::Dhash, Self::Phash, Self::Blockhash];
+
+    /// Compute the 64-bit hash of an image.
+    pub fn hash(self, image: &DynamicImage) -> u64 {
+        match self {
+            Self::Ahash => {
+                let pixels = shrink(image, 8, 8).into_raw();
+                let mean = pixels.iter().map(|p| *p as u32).sum::<u32>() / pixels.len() as u32;
+                to_bits(pixels.iter().map(|p| *p as u32 > mean))
+            }
+            Self::Dhash => {
+                let thumbnail = shrink(image, 9, 8);
+                to_bits((0..8).flat_map(|y| {
+                    let thumbnail = &thumbnail;
+                    (0..8).map(move |x| {
+                        thumbnail.get_pixel(x, y)[0] > thumbnail.get_pixel(x + 1, y)[0]
+                    })
+                }))
+            }
+            Self::Phash => {
+                let thumbnail = shrink(image, PHASH_SIZE as u32, PHASH_SIZE as u32);
+                let cosines: Vec<Vec<f64>> = (0..PHASH_FREQUENCIES)
+                    .map(|u| {
+                        (0..PHASH_SIZE)
+                            .map(|x| {
+                                ((2 * x + 1) as f64 * u as f64 * PI / (2 * PHASH_SIZE) as f64).cos()
+                            })
+                            .collect()
+                    })
+                    .collect();
+                let mut coefficients = Vec::with_capacity(PHASH_FREQUENCIES * PHASH_FREQUENCIES);
+                for v in 0..PHASH_FREQUENCIES {
+                    for u in 0..PHASH_FREQUENCIES {
+                        let mut sum = 0.0;
+                        for y in 0..PHASH_SIZE {
+                            for x in 0..PHASH_SIZE {
+                                sum += thumbnail.get_pixel(x as u32, y as u32)[0] as f64
+                                    * cosines[u][x]
+                                    * cosines[v][y];
+                            }
+                        }
+                        coefficients.push(sum);
+                    }
+                }
+                // The DC term only reflects overall brightness, so leave it out of the median.
+                let mut sorted = coefficients[1..].to_vec();
+                sorted.sort_by(f64::total_cmp);
+                let median = sorted[sorted.len() / 2];
+                to_bits(coefficients.iter().map(|c| *c > median))
+            }
+            Self::Blockhash => blockhash(image),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Ahash => "aHash",
+            Self::Dhash => "dHash",
+            Self::Phash => "pHash",
+            Self::Blockhash => "blockhash",
+        }
+    }
+}
+
+/// Count the number of differing bits between two hashes.
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+pub fn to_hex(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
+fn shrink(image: &DynamicImage, width: u32, height: u32) -> GrayImage {
+    image
+        .resize_exact(width, height, FilterType::Triangle)
+        .into_luma8()
+}
+
+/// Compute the blockhash of an image, matching the reference implementation.
+fn blockhash(image: &DynamicImage) -> u64 {
+    let image = image.to_rgba8();
+    let (width, height) = image.dimensions();
+    let (columns, rows) = (blockhash_spans(width), blockhash_spans(height));
+
+    let mut blocks = vec![0.0; BLOCKHASH_GRID * BLOCKHASH_GRID];
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let [r, g, b, a] = pixel.0.map(f64::from);
+        let value = if a == 0.0 { 765.0 } else { r + g + b };
+        let (left, right, left_weight) = columns[x as usize];
+        let (top, bottom, top_weight) = rows[y as usize];
+        for (row, row_weight) in [(top, top_weight), (bottom, 1.0 - top_weight)] {
+            for (column, column_weight) in [(left, left_weight), (right, 1.0 - left_weight)] {
+                blocks[row * BLOCKHASH_GRID + column] += value * row_weight * column_weight;
+            }
+        }
+    }
+
+    // Compare blocks against the median of their quarter of the image to stay robust
+    // against gradients across the whole picture. Blocks equal to a bright median are
+    // counted as set so that mostly white images still produce distinct hashes.
+    let pixels_per_block =
+        (width as f64 / BLOCKHASH_GRID as f64) * (height as f64 / BLOCKHASH_GRID as f64);
+    let half_block_value = pixels_per_block * 256.0 * 3.0 / 2.0;
+    to_bits(blocks.chunks(blocks.len() / 4).flat_map(|band| {
+        let mut sorted = band.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let middle = sorted.len() / 2;
+        let median = (sorted[middle - 1] + sorted[middle]) / 2.0;
+        band.iter()
+            .map(move |v| *v > median || ((v - median).abs() < 1.0 && median > half_block_value))
+            .collect::<Vec<_>>()
+    }))
+}
+
+/// The blocks each pixel along an axis of the given length falls into, as the first
+/// block, the second block and the share of the pixel in the first. When the length
+/// doesn't divide evenly, pixels on the boundary between two blocks are split between them.
+fn blockhash_spans(length: u32) -> Vec<(usize, usize, f64)> {
+    let block = length as f64 / BLOCKHASH_GRID as f64;
+    (0..length)
+        .map(|i| {
+            let first = (i as f64 / block).floor() as usize;
+            if length as usize % BLOCKHASH_GRID == 0 {
+                return (first, first, 1.0);
+            }
+            let end = (i + 1) as f64 % block;
+            if end.trunc() > 0.0 || i + 1 == length {
+                (first, first, 1.0)
+            } else {
+                let second = (i as f64 / block).ceil() as usize;
+                (first, second, 1.0 - end.fract())
+            }
+        })
+        .collect()
+}
+
+fn to_bits(bits: impl Iterator<Item = bool>) -> u64 {
+    bits.fold(0, |hash, bit| (hash << 1) | bit as u64)
+}
+
+impl ExecutableCommand for HashCommand {
+    fn run(self) -> Result<()> {
+        let mut algorithms = if self.algorithms.is_empty() {
+            PerceptualHash::ALL.to_vec()
+        } else {
+            self.algorithms
+        };
+        algorithms.sort();
+        algorithms.dedup();
+
+        let mut reports = Vec::with_capacity(self.input_paths.len());
+        for input_path in self.input_paths {
+            if !input_path.exists() {
+                bail!(INPUT_FILE_DOES_NOT_EXIST);
+            }
+            if !input_path.is_file() {
+                bail!(INPUT_IS_NOT_FILE);
+            }
+            let image = ImageReader::open(&input_path)
+                .context(ERROR_IMGREAD_CTX)?
+                .decode()
+                .context(ERROR_IMGDECODE_CTX)?;
+            let hashes = algorithms
+                .iter()
+                .map(|algorithm| (algorithm.name().to_string(), to_hex(algorithm.hash(&image))))
+                .collect();
+            reports.push(HashReport {
+                path: input_path,
+                hashes,
+            });
+        }
+
+        match self.format {
+            HashOutputFormat::Text => {
+                for report in reports {
+                    println!("{}", report.path.display());
+                    for algorithm in &algorithms {
+                        println!(
+                            "  * {}: {}",
+                            algorithm.name(),
+                            report.hashes[algorithm.name()]
+                        );
+                    }
+                }
+            }
+            HashOutputFormat::Json => println!("{}", serde_json::to_string_pretty(&reports)?),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+use clap::ValueEnum;
+use image::{ColorType, DynamicImage, Rgba};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Convert an image into the given colour type, keeping the pixel data
+/// as close to the original as the target type allows.
+pub fn into_color_type(image: DynamicImage, color: ColorType) -> DynamicImage {
+    match color {
+        ColorType::L8 => DynamicImage::ImageLuma8(image.into_luma8()),
+        ColorType::La8 => DynamicImage::ImageLumaA8(image.into_luma_alpha8()),
+        ColorType::Rgb8 => DynamicImage::ImageRgb8(image.into_rgb8()),
+        ColorType::L16 => DynamicImage::ImageLuma16(image.into_luma16()),
+        ColorType::La16 => DynamicImage::ImageLumaA16(image.into_luma_alpha16()),
+        ColorType::Rgb16 => DynamicImage::ImageRgb16(image.into_rgb16()),
+        ColorType::Rgba16 => DynamicImage::ImageRgba16(image.into_rgba16()),
+        ColorType::Rgb32F => DynamicImage::ImageRgb32F(image.into_rgb32f()),
+        ColorType::Rgba32F => DynamicImage::ImageRgba32F(image.into_rgba32f()),
+        _ => DynamicImage::ImageRgba8(image.into_rgba8()),
+    }
+}
+
+/// Get the colour (non-grayscale) equivalent of a colour type while
+/// keeping its bit depth and alpha channel.
+pub fn color_equivalent(color: ColorType) -> ColorType {
+    match color {
+        ColorType::L8 => ColorType::Rgb8,
+        ColorType::La8 => ColorType::Rgba8,
+        ColorType::L16 => ColorType::Rgb16,
+        ColorType::La16 => ColorType::Rgba16,
+        other => other,
+    }
+}
+
+/// Get the equivalent of a colour type that has an alpha channel.
+pub fn with_alpha(color: ColorType) -> ColorType {
+    match color {
+        ColorType::L8 => ColorType::La8,
+        ColorType::Rgb8 => ColorType::Rgba8,
+        ColorType::L16 => ColorType::La16,
+        ColorType::Rgb16 => ColorType::Rgba16,
+        ColorType::Rgb32F => ColorType::Rgba32F,
+        other => other,
+    }
+}
+
+/// Get the equivalent of a colour type that has no alpha channel.
+pub fn without_alpha(color: ColorType) -> ColorType {
+    match color {
+        ColorType::La8 => ColorType::L8,
+        ColorType::Rgba8 => ColorType::Rgb8,
+        ColorType::La16 => ColorType::L16,
+        ColorType::Rgba16 => ColorType::Rgb16,
+        ColorType::Rgba32F => ColorType::Rgb32F,
+        other => other,
+    }
+}
+
+/// Get the colour type to use when layering an image of colour type `layer` onto
+/// one of colour type `base`, promoting grayscale bases to colour when needed.
+pub fn layered_color(base: ColorType, layer: ColorType) -> ColorType {
+    match layer {
+        ColorType::L8 | ColorType::La8 | ColorType::L16 | ColorType::La16 => base,
+        _ => color_equivalent(base),
+    }
+}
+
+/// A rectangular area of an image.
+#[derive(Debug, Clone, Copy)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Parse a pixel coordinate formatted as 'x,y'.
+pub fn parse_point(value: &str) -> Result<(u32, u32), String> {
+    match parse_u32_list(value)?.as_slice() {
+        [x, y] => Ok((*x, *y)),
+        _ => Err("expected a point formatted as 'x,y'".to_string()),
+    }
+}
+
+/// Parse a region formatted as 'x,y,width,height'.
+pub fn parse_region(value: &str) -> Result<Region, String> {
+    match parse_u32_list(value)?.as_slice() {
+        [x, y, width, height] => Ok(Region {
+            x: *x,
+            y: *y,
+            width: *width,
+            height: *height,
+        }),
+        _ => Err("expected a region formatted as 'x,y,width,height'".to_string()),
+    }
+}
+
+/// Parse a signed offset formatted as 'x,y'.
+pub fn parse_offset(value: &str) -> Result<(i32, i32), String> {
+    let values = value
+        .split(',')
+        .map(|v| v.trim().parse::<i32>().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    match values.as_slice() {
+        [x, y] => Ok((*x, *y)),
+        _ => Err("expected an offset formatted as 'x,y'".to_string()),
+    }
+}
+
+fn parse_u32_list(value: &str) -> Result<Vec<u32>, String> {
+    value
+        .split(',')
+        .map(|v| v.trim().parse::<u32>().map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// Where an item is anchored within a larger area.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Gravity {
+    /// The top left corner.
+    NorthWest,
+
+    /// The middle of the top edge.
+    North,
+
+    /// The top right corner.
+    NorthEast,
+
+    /// The middle of the left edge.
+    West,
+
+    /// The centre.
+    Center,
+
+    /// The middle of the right edge.
+    East,
+
+    /// The bottom left corner.
+    SouthWest,
+
+    /// The middle of the bottom edge.
+    South,
+
+    /// The bottom right corner.
+    SouthEast,
+}
+
+impl Gravity {
+    /// Get the top left position of an item of the given size within an area, where the
+    /// offset moves the item inwards from the edges it is anchored to.
+    pub fn position(self, area: (u32, u32), item: (u32, u32), offset: (i32, i32)) -> (i64, i64) {
+        let (column, row) = match self {
+            Self::NorthWest => (0, 0),
+            Self::North => (1, 0),
+            Self::NorthEast => (2, 0),
+            Self::West => (0, 1),
+            Self::Center => (1, 1),
+            Self::East => (2, 1),
+            Self::SouthWest => (0, 2),
+            Self::South => (1, 2),
+            Self::SouthEast => (2, 2),
+        };
+        let place = |anchor: u8, area: u32, item: u32, offset: i32| {
+            let (area, item, offset) = (area as i64, item as i64, offset as i64);
+            match anchor {
+                0 => offset,
+                1 => (area - item) / 2 + offset,
+                _ => area - item - offset,
+            }
+        };
+        (
+            place(column, area.0, item.0, offset.0),
+            place(row, area.1, item.1, offset.1),
+        )
+    }
+}
+
+/// Parse a hex colour formatted as '#rgb', '#rrggbb' or '#rrggbbaa' (the '#' is optional).
+pub fn parse_color(value: &str) -> Result<Rgba<u8>, String> {
+    let hex = value.trim().trim_start_matches('#');
+    let expanded = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect::<String>() + "ff",
+        6 => format!("{hex}ff"),
+        8 => hex.to_string(),
+        _ => return Err(format!("'{value}' is not a valid hex colour")),
+    };
+    let channel = |i: usize| {
+        u8::from_str_radix(&expanded[i * 2..i * 2 + 2], 16)
+            .map_err(|_| format!("'{value}' is not a valid hex colour"))
+    };
+    Ok(Rgba([channel(0)?, channel(1)?, channel(2)?, channel(3)?]))
+}
+
+/// Expand a list of file and directory paths into the files they contain,
+/// optionally descending into subdirectories. Results are sorted for stable output.
+pub fn collect_files(paths: &[PathBuf], recursive: bool) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending: Vec<(PathBuf, bool)> = paths.iter().map(|p| (p.clone(), true)).collect();
+    while let Some((path, top_level)) = pending.pop() {
+        if path.is_dir() {
+            if !top_level && !recursive {
+                continue;
+            }
+            for entry in fs::read_dir(&path)? {
+                pending.push((entry?.path(), false));
+            }
+        } else if path.is_file() {
+            files.push(path);
+        } else if top_level {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("unable to find file or directory at {}", path.display()),
+            ));
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
@@ -1,10 +1,25 @@
+mod checksum;
+mod compare;
 mod details;
 mod dimensions;
+mod dupes;
+mod hash;
+mod helpers;
 mod modify;
+mod palette;
+mod stats;
+mod validate;
 
+use self::checksum::ChecksumCommand;
+use self::compare::CompareCommand;
 use self::details::DetailsCommand;
 use self::dimensions::DimensionsCommand;
+use self::dupes::DupesCommand;
+use self::hash::HashCommand;
 use self::modify::ModifyCommandBase;
+use self::palette::PaletteCommand;
+use self::stats::StatsCommand;
+use self::validate::ValidateCommand;
 
 use anyhow::Result;
 use clap::Parser;
@@ -19,9 +34,16 @@ pub struct ProcessCommandRoot {
 
 #[derive(Debug, Parser)]
 pub enum Commands {
+    Checksum(ChecksumCommand),
+    Compare(CompareCommand),
     Details(DetailsCommand),
     Dimensions(DimensionsCommand),
+    Dupes(DupesCommand),
+    Hash(HashCommand),
     Modify(ModifyCommandBase),
+    Palette(PaletteCommand),
+    Stats(StatsCommand),
+    Validate(ValidateCommand),
 }
 
 pub trait ExecutableCommand {
@@ -32,9 +54,16 @@ pub trait ExecutableCommand {
 impl ExecutableCommand for ProcessCommandRoot {
     fn run(self) -> Result<()> {
         match self.cmd {
+            Commands::Checksum(cmd) => cmd.run(),
+            Commands::Compare(cmd) => cmd.run(),
             Commands::Details(cmd) => cmd.run(),
             Commands::Dimensions(cmd) => cmd.run(),
+            Commands::Dupes(cmd) => cmd.run(),
+            Commands::Hash(cmd) => cmd.run(),
             Commands::Modify(cmd) => cmd.run(),
+            Commands::Palette(cmd) => cmd.run(),
+            Commands::Stats(cmd) => cmd.run(),
+            Commands::Validate(cmd) => cmd.run(),
         }
     }
 }
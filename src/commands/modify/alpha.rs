@@ -0,0 +1,191 @@
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::{
+    color_equivalent, into_color_type, parse_color, with_alpha, without_alpha,
+};
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::imageops::{self, FilterType};
+use image::{ColorType, DynamicImage, ImageFormat, ImageReader, Rgba};
+use std::path::PathBuf;
+
+/// Remove, add, extract or adjust the alpha channel of an image.
+#[derive(Debug, Clone, Parser)]
+pub struct AlphaCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The operation to perform on the alpha channel.
+    #[arg(long = "operation")]
+    pub operation: AlphaOperation,
+
+    /// The opaque colour to flatten the image onto.
+    #[arg(long = "background", default_value = "#ffffff", value_parser = parse_color)]
+    pub background: Rgba<u8>,
+
+    /// A path on disk to a grayscale image to use as the alpha channel, where white is opaque.
+    /// The mask is stretched to the input's dimensions if they differ.
+    #[arg(long = "mask", required_if_eq("operation", "set"))]
+    pub mask_path: Option<PathBuf>,
+
+    /// The amount (0.0 to 1.0) to multiply the opacity of every pixel by.
+    #[arg(long = "opacity", required_if_eq("operation", "opacity"))]
+    pub opacity: Option<f32>,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum AlphaOperation {
+    /// Remove the alpha channel by compositing the image onto the background colour.
+    Flatten,
+
+    /// Add an alpha channel with every pixel made fully opaque.
+    Add,
+
+    /// Output the alpha channel on its own as a grayscale image.
+    Extract,
+
+    /// Replace the alpha channel with the mask image.
+    Set,
+
+    /// Scale the opacity of the whole image.
+    Opacity,
+
+    /// Multiply the colour channels by alpha.
+    Premultiply,
+
+    /// Divide the colour channels by alpha, reversing premultiplication.
+    Unpremultiply,
+}
+
+impl ExecutableCommand for AlphaCommand {
+    fn run(self) -> Result<()> {
+        let inputs = [Some(&self.input_path), self.mask_path.as_ref()];
+        if inputs.iter().flatten().any(|path| !path.exists()) {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if inputs.iter().flatten().any(|path| !path.is_file()) {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+        if self
+            .opacity
+            .is_some_and(|opacity| !(0.0..=1.0).contains(&opacity))
+        {
+            bail!("opacity must be between 0.0 and 1.0");
+        }
+        if self.background[3] != u8::MAX {
+            bail!("background colour must be fully opaque");
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let decode = |path: &PathBuf| -> Result<DynamicImage> {
+            ImageReader::open(path)
+                .context(ERROR_IMGREAD_CTX)?
+                .decode()
+                .context(ERROR_IMGDECODE_CTX)
+        };
+        let image = decode(&self.input_path)?;
+        let mask = self.mask_path.as_ref().map(decode).transpose()?;
+
+        let color = image.color();
+        let mut buffer = image.into_rgba32f();
+
+        let output_color = match self.operation {
+            AlphaOperation::Flatten => {
+                let background = self.background.0.map(|c| c as f32 / 255.0);
+                for pixel in buffer.pixels_mut() {
+                    let alpha = pixel[3];
+                    for c in 0..3 {
+                        pixel[c] = pixel[c] * alpha + background[c] * (1.0 - alpha);
+                    }
+                    pixel[3] = 1.0;
+                }
+                let [r, g, b, _] = self.background.0;
+                if r == g && g == b {
+                    without_alpha(color)
+                } else {
+                    without_alpha(color_equivalent(color))
+                }
+            }
+            AlphaOperation::Add => {
+                for pixel in buffer.pixels_mut() {
+                    pixel[3] = 1.0;
+                }
+                with_alpha(color)
+            }
+            AlphaOperation::Extract => {
+                for pixel in buffer.pixels_mut() {
+                    *pixel = Rgba([pixel[3], pixel[3], pixel[3], 1.0]);
+                }
+                if color.bytes_per_pixel() / color.channel_count() == 1 {
+                    ColorType::L8
+                } else {
+                    ColorType::L16
+                }
+            }
+            AlphaOperation::Set => {
+                let (width, height) = buffer.dimensions();
+                let mut mask = mask
+                    .expect("mask is required for the set operation")
+                    .to_luma32f();
+                if mask.dimensions() != (width, height) {
+                    mask = imageops::resize(&mask, width, height, FilterType::Triangle);
+                }
+                for (pixel, value) in buffer.pixels_mut().zip(mask.pixels()) {
+                    pixel[3] = value[0].clamp(0.0, 1.0);
+                }
+                with_alpha(color)
+            }
+            AlphaOperation::Opacity => {
+                let opacity = self
+                    .opacity
+                    .expect("opacity is required for the opacity operation");
+                for pixel in buffer.pixels_mut() {
+                    pixel[3] *= opacity;
+                }
+                with_alpha(color)
+            }
+            AlphaOperation::Premultiply => {
+                for pixel in buffer.pixels_mut() {
+                    for c in 0..3 {
+                        pixel[c] *= pixel[3];
+                    }
+                }
+                color
+            }
+            AlphaOperation::Unpremultiply => {
+                for pixel in buffer.pixels_mut() {
+                    if pixel[3] > 0.0 {
+                        for c in 0..3 {
+                            pixel[c] = (pixel[c] / pixel[3]).min(1.0);
+                        }
+                    }
+                }
+                color
+            }
+        };
+
+        into_color_type(DynamicImage::ImageRgba32F(buffer), output_color)
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
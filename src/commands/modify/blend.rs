@@ -0,0 +1,210 @@
+use super::composite::composite;
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::{into_color_type, layered_color};
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageFormat, ImageReader};
+use std::path::PathBuf;
+
+/// Blend a second image onto an image using a blend mode.
+///
+/// The layer and mask are stretched to the input's dimensions if they differ.
+#[derive(Debug, Clone, Parser)]
+pub struct BlendCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// A path on disk to the image that should be blended onto the input.
+    #[arg(long = "layer")]
+    pub layer_path: PathBuf,
+
+    /// How the colours of the layer and input are combined.
+    #[arg(long = "mode")]
+    pub mode: BlendMode,
+
+    /// The opacity (0.0 to 1.0) of the layer.
+    #[arg(long = "opacity", default_value_t = 1.0)]
+    pub opacity: f32,
+
+    /// A path on disk to a grayscale image controlling where the layer is applied,
+    /// from not at all (black) to fully (white).
+    #[arg(long = "mask")]
+    pub mask_path: Option<PathBuf>,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum BlendMode {
+    /// Show the layer as is.
+    Normal,
+
+    /// Multiply the colours, always darkening.
+    Multiply,
+
+    /// Multiply the inverted colours, always lightening.
+    Screen,
+
+    /// Multiply or screen depending on the input, increasing contrast.
+    Overlay,
+
+    /// Darken or lighten depending on the layer, like shining a diffused light.
+    SoftLight,
+
+    /// Multiply or screen depending on the layer, like shining a harsh light.
+    HardLight,
+
+    /// The absolute difference between the colours.
+    Difference,
+
+    /// Like difference but lower in contrast.
+    Exclusion,
+
+    /// Keep the darker of the colours.
+    Darken,
+
+    /// Keep the lighter of the colours.
+    Lighten,
+
+    /// Brighten the input to reflect the layer.
+    ColorDodge,
+
+    /// Darken the input to reflect the layer.
+    ColorBurn,
+
+    /// Add the colours together.
+    Add,
+
+    /// Subtract the layer from the input.
+    Subtract,
+}
+
+impl BlendMode {
+    /// Blend a backdrop channel value with a source channel value, both between 0.0 and 1.0.
+    fn blend(self, backdrop: f32, source: f32) -> f32 {
+        let (b, s) = (backdrop.clamp(0.0, 1.0), source.clamp(0.0, 1.0));
+        let multiply = |b: f32, s: f32| b * s;
+        let screen = |b: f32, s: f32| b + s - b * s;
+        let hard_light = |b: f32, s: f32| {
+            if s <= 0.5 {
+                multiply(b, 2.0 * s)
+            } else {
+                screen(b, 2.0 * s - 1.0)
+            }
+        };
+        match self {
+            Self::Normal => s,
+            Self::Multiply => multiply(b, s),
+            Self::Screen => screen(b, s),
+            Self::Overlay => hard_light(s, b),
+            Self::SoftLight => {
+                if s <= 0.5 {
+                    b - (1.0 - 2.0 * s) * b * (1.0 - b)
+                } else {
+                    let d = if b <= 0.25 {
+                        ((16.0 * b - 12.0) * b + 4.0) * b
+                    } else {
+                        b.sqrt()
+                    };
+                    b + (2.0 * s - 1.0) * (d - b)
+                }
+            }
+            Self::HardLight => hard_light(b, s),
+            Self::Difference => (b - s).abs(),
+            Self::Exclusion => b + s - 2.0 * b * s,
+            Self::Darken => b.min(s),
+            Self::Lighten => b.max(s),
+            Self::ColorDodge => match (b, s) {
+                (0.0, _) => 0.0,
+                (_, 1.0) => 1.0,
+                _ => (b / (1.0 - s)).min(1.0),
+            },
+            Self::ColorBurn => match (b, s) {
+                (1.0, _) => 1.0,
+                (_, 0.0) => 0.0,
+                _ => 1.0 - ((1.0 - b) / s).min(1.0),
+            },
+            Self::Add => (b + s).min(1.0),
+            Self::Subtract => (b - s).max(0.0),
+        }
+    }
+}
+
+impl ExecutableCommand for BlendCommand {
+    fn run(self) -> Result<()> {
+        let inputs = [
+            Some(&self.input_path),
+            Some(&self.layer_path),
+            self.mask_path.as_ref(),
+        ];
+        if inputs.iter().flatten().any(|path| !path.exists()) {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if inputs.iter().flatten().any(|path| !path.is_file()) {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+        if !(0.0..=1.0).contains(&self.opacity) {
+            bail!("opacity must be between 0.0 and 1.0");
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let decode = |path: &PathBuf| -> Result<DynamicImage> {
+            ImageReader::open(path)
+                .context(ERROR_IMGREAD_CTX)?
+                .decode()
+                .context(ERROR_IMGDECODE_CTX)
+        };
+        let image = decode(&self.input_path)?;
+        let layer = decode(&self.layer_path)?;
+        let mask = self.mask_path.as_ref().map(decode).transpose()?;
+
+        let color = layered_color(image.color(), layer.color());
+        let mut buffer = image.into_rgba32f();
+        let (width, height) = buffer.dimensions();
+        let mut layer = layer.into_rgba32f();
+        if layer.dimensions() != (width, height) {
+            layer = imageops::resize(&layer, width, height, FilterType::Lanczos3);
+        }
+        let mask = mask.map(|mask| {
+            let mask = mask.to_luma32f();
+            if mask.dimensions() != (width, height) {
+                imageops::resize(&mask, width, height, FilterType::Triangle)
+            } else {
+                mask
+            }
+        });
+
+        composite(
+            &mut buffer,
+            &layer,
+            (0, 0),
+            self.opacity,
+            mask.as_ref(),
+            |backdrop, source| self.mode.blend(backdrop, source),
+        );
+
+        into_color_type(DynamicImage::ImageRgba32F(buffer), color)
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use super::{channel_bytes, color_type};
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::into_color_type;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::{ArgGroup, Parser};
+use image::{DynamicImage, ImageFormat, ImageReader, Rgba, Rgba32FImage};
+use std::path::PathBuf;
+
+/// Build an RGB or RGBA image from separate grayscale images, one per channel.
+///
+/// Every input must have the same dimensions. Colour inputs are converted to grayscale
+/// first, and the output uses the highest bit depth of the inputs.
+#[derive(Debug, Clone, Parser)]
+#[clap(group(ArgGroup::new("channels").required(true).multiple(true)))]
+pub struct ChannelsMergeCommand {
+    /// A path on disk to the image to use as the red channel.
+    /// The channel is filled with black if omitted.
+    #[arg(long = "red", group = "channels")]
+    pub red_path: Option<PathBuf>,
+
+    /// A path on disk to the image to use as the green channel.
+    /// The channel is filled with black if omitted.
+    #[arg(long = "green", group = "channels")]
+    pub green_path: Option<PathBuf>,
+
+    /// A path on disk to the image to use as the blue channel.
+    /// The channel is filled with black if omitted.
+    #[arg(long = "blue", group = "channels")]
+    pub blue_path: Option<PathBuf>,
+
+    /// A path on disk to the image to use as the alpha channel.
+    /// An RGB image without alpha is produced if omitted.
+    #[arg(long = "alpha", group = "channels")]
+    pub alpha_path: Option<PathBuf>,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+impl ExecutableCommand for ChannelsMergeCommand {
+    fn run(self) -> Result<()> {
+        let inputs = [
+            &self.red_path,
+            &self.green_path,
+            &self.blue_path,
+            &self.alpha_path,
+        ];
+        if inputs.into_iter().flatten().any(|path| !path.exists()) {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if inputs.into_iter().flatten().any(|path| !path.is_file()) {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let mut channels = Vec::with_capacity(inputs.len());
+        for path in inputs {
+            let image = path
+                .as_ref()
+                .map(|path| -> Result<DynamicImage> {
+                    ImageReader::open(path)
+                        .context(ERROR_IMGREAD_CTX)?
+                        .decode()
+                        .context(ERROR_IMGDECODE_CTX)
+                })
+                .transpose()?;
+            channels.push(image);
+        }
+
+        let mut images = channels.iter().flatten();
+        let first = images.next().expect("at least one channel is required");
+        let dimensions = (first.width(), first.height());
+        if images.any(|image| (image.width(), image.height()) != dimensions) {
+            bail!("all channel images must have the same dimensions");
+        }
+        let bytes = channels
+            .iter()
+            .flatten()
+            .map(|image| channel_bytes(image.color()))
+            .max()
+            .unwrap_or(1);
+
+        let planes: Vec<Option<Vec<f32>>> = channels
+            .into_iter()
+            .map(|image| image.map(|image| image.to_luma32f().into_raw()))
+            .collect();
+        let (width, height) = dimensions;
+        let buffer = Rgba32FImage::from_fn(width, height, |x, y| {
+            let index = (y * width + x) as usize;
+            let value = |channel: usize, default: f32| {
+                planes[channel]
+                    .as_ref()
+                    .map_or(default, |plane| plane[index])
+            };
+            Rgba([value(0, 0.0), value(1, 0.0), value(2, 0.0), value(3, 1.0)])
+        });
+
+        into_color_type(
+            DynamicImage::ImageRgba32F(buffer),
+            color_type(bytes, self.alpha_path.is_some()),
+        )
+        .save_with_format(self.output_path, output_format)
+        .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+mod merge;
+mod split;
+mod swizzle;
+
+use self::merge::ChannelsMergeCommand;
+use self::split::ChannelsSplitCommand;
+use self::swizzle::ChannelsSwizzleCommand;
+
+use crate::commands::ExecutableCommand;
+use anyhow::Result;
+use clap::Parser;
+use image::ColorType;
+
+/// Split, merge and rearrange the colour channels of images.
+#[derive(Debug, Parser)]
+pub struct ChannelsCommand {
+    #[clap(subcommand)]
+    subcommand: ChannelsSubcommand,
+}
+
+#[derive(Debug, Parser)]
+pub enum ChannelsSubcommand {
+    Merge(ChannelsMergeCommand),
+    Split(ChannelsSplitCommand),
+    Swizzle(ChannelsSwizzleCommand),
+}
+
+impl ExecutableCommand for ChannelsCommand {
+    fn run(self) -> Result<()> {
+        match self.subcommand {
+            ChannelsSubcommand::Merge(cmd) => cmd.run(),
+            ChannelsSubcommand::Split(cmd) => cmd.run(),
+            ChannelsSubcommand::Swizzle(cmd) => cmd.run(),
+        }
+    }
+}
+
+/// The number of bytes each channel of a colour type uses (1, 2 or 4).
+fn channel_bytes(color: ColorType) -> u8 {
+    color.bytes_per_pixel() / color.channel_count()
+}
+
+/// Get the colour type with `bytes` per channel that holds colour, and alpha if requested.
+fn color_type(bytes: u8, alpha: bool) -> ColorType {
+    match (bytes, alpha) {
+        (1, false) => ColorType::Rgb8,
+        (1, true) => ColorType::Rgba8,
+        (2, false) => ColorType::Rgb16,
+        (2, true) => ColorType::Rgba16,
+        (_, false) => ColorType::Rgb32F,
+        (_, true) => ColorType::Rgba32F,
+    }
+}
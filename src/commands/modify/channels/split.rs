@@ -0,0 +1,96 @@
+use super::{channel_bytes, color_type};
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::into_color_type;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use image::{ColorType, DynamicImage, ImageFormat, ImageReader, Rgba, Rgba32FImage};
+use std::path::{Path, PathBuf};
+
+/// Save each channel of an image as its own grayscale image.
+///
+/// 8 and 16-bit channels are saved as grayscale images of the same depth. As there is
+/// no 32-bit grayscale image type, 32-bit channels are saved as RGB images with the
+/// channel's values repeated across all three colour channels.
+#[derive(Debug, Clone, Parser)]
+pub struct ChannelsSplitCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output images should be placed. The name of each
+    /// channel is appended to the file name, so 'texture.png' produces 'texture_r.png',
+    /// 'texture_g.png' and so on. Grayscale inputs produce a single '_l' channel.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// Overwrite any existing files at the output paths.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+impl ExecutableCommand for ChannelsSplitCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(&self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        let color = image.color();
+        let channels: &[(usize, &str)] = match color {
+            ColorType::L8 | ColorType::L16 => &[(0, "l")],
+            ColorType::La8 | ColorType::La16 => &[(0, "l"), (3, "a")],
+            ColorType::Rgb8 | ColorType::Rgb16 | ColorType::Rgb32F => {
+                &[(0, "r"), (1, "g"), (2, "b")]
+            }
+            _ => &[(0, "r"), (1, "g"), (2, "b"), (3, "a")],
+        };
+        let outputs: Vec<PathBuf> = channels
+            .iter()
+            .map(|(_, name)| channel_path(&self.output_path, name))
+            .collect();
+        if !self.overwrite && outputs.iter().any(|path| path.exists()) {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+
+        let output_color = match channel_bytes(color) {
+            1 => ColorType::L8,
+            2 => ColorType::L16,
+            bytes => color_type(bytes, false),
+        };
+        let buffer = image.into_rgba32f();
+        for ((index, _), path) in channels.iter().zip(outputs) {
+            let channel = Rgba32FImage::from_fn(buffer.width(), buffer.height(), |x, y| {
+                let value = buffer.get_pixel(x, y)[*index];
+                Rgba([value, value, value, 1.0])
+            });
+            into_color_type(DynamicImage::ImageRgba32F(channel), output_color)
+                .save_with_format(path, output_format)
+                .context(ERROR_IMGSAVE_CTX)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Append `_name` to the file stem of `path`, keeping its extension.
+fn channel_path(path: &Path, name: &str) -> PathBuf {
+    let mut file_name = path.file_stem().unwrap_or_default().to_os_string();
+    file_name.push(format!("_{name}"));
+    if let Some(extension) = path.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+    path.with_file_name(file_name)
+}
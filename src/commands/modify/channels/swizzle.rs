@@ -0,0 +1,109 @@
+use super::{channel_bytes, color_type};
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::into_color_type;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use std::path::PathBuf;
+
+/// Rearrange, duplicate or replace the channels of an image.
+#[derive(Debug, Clone, Parser)]
+pub struct ChannelsSwizzleCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The source of each output channel, given as 3 characters for an RGB image or 4 for
+    /// an RGBA image. Each character is 'r', 'g', 'b' or 'a' to copy that input channel,
+    /// or '0' or '1' to fill the channel with black or white (e.g. 'bgra' or 'rrr1').
+    #[arg(long = "pattern", value_parser = parse_pattern)]
+    pub pattern: Pattern,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+/// Where each output channel takes its value from.
+#[derive(Debug, Clone)]
+pub struct Pattern(Vec<ChannelSource>);
+
+#[derive(Debug, Clone, Copy)]
+enum ChannelSource {
+    Channel(usize),
+    Constant(f32),
+}
+
+fn parse_pattern(value: &str) -> Result<Pattern, String> {
+    let sources = value
+        .trim()
+        .chars()
+        .map(|c| match c.to_ascii_lowercase() {
+            'r' => Ok(ChannelSource::Channel(0)),
+            'g' => Ok(ChannelSource::Channel(1)),
+            'b' => Ok(ChannelSource::Channel(2)),
+            'a' => Ok(ChannelSource::Channel(3)),
+            '0' => Ok(ChannelSource::Constant(0.0)),
+            '1' => Ok(ChannelSource::Constant(1.0)),
+            other => Err(format!(
+                "unknown channel '{other}', expected one of 'r', 'g', 'b', 'a', '0' or '1'"
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if !(3..=4).contains(&sources.len()) {
+        return Err("the pattern must be 3 or 4 characters long".to_string());
+    }
+    Ok(Pattern(sources))
+}
+
+impl ExecutableCommand for ChannelsSwizzleCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        let color = image.color();
+        let mut buffer = image.into_rgba32f();
+
+        let sources = &self.pattern.0;
+        for pixel in buffer.pixels_mut() {
+            let input = pixel.0;
+            pixel.0 = std::array::from_fn(|c| match sources.get(c) {
+                Some(ChannelSource::Channel(index)) => input[*index],
+                Some(ChannelSource::Constant(value)) => *value,
+                None => 1.0,
+            });
+        }
+
+        into_color_type(
+            DynamicImage::ImageRgba32F(buffer),
+            color_type(channel_bytes(color), sources.len() == 4),
+        )
+        .save_with_format(self.output_path, output_format)
+        .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
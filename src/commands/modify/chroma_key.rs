@@ -0,0 +1,153 @@
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::{into_color_type, parse_color, with_alpha};
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use image::{ColorType, DynamicImage, ImageFormat, ImageReader, Rgba};
+use std::path::PathBuf;
+
+/// Make pixels close to a key colour transparent, such as the green screen
+/// or plain white backdrop behind a product shot.
+///
+/// The output must be saved in a format that supports transparency.
+#[derive(Debug, Clone, Parser)]
+pub struct ChromaKeyCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The background colour to remove.
+    #[arg(long = "color", default_value = "#00ff00", value_parser = parse_color)]
+    pub color: Rgba<u8>,
+
+    /// How far (0.0 to 1.0) a colour can be from the key colour and still be made fully transparent.
+    #[arg(long = "tolerance", default_value_t = 0.1)]
+    pub tolerance: f32,
+
+    /// The distance (0.0 to 1.0) beyond the tolerance over which pixels fade from
+    /// transparent to opaque, softening the edges of the subject.
+    #[arg(long = "feather", default_value_t = 0.05)]
+    pub feather: f32,
+
+    /// How strongly (0.0 to 1.0) to remove the key colour's tint from the pixels
+    /// that remain, such as green light reflected onto the subject's edges.
+    #[arg(long = "spill", default_value_t = 0.0)]
+    pub spill: f32,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+impl ExecutableCommand for ChromaKeyCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+        for (name, value) in [
+            ("tolerance", self.tolerance),
+            ("feather", self.feather),
+            ("spill", self.spill),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                bail!("{name} must be between 0.0 and 1.0");
+            }
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let Some(output_color) = alpha_color_type(output_format) else {
+            bail!(
+                "the {output_format:?} format cannot store transparency, use a format such as PNG or WebP instead"
+            );
+        };
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        let color = image.color();
+        let mut buffer = image.into_rgba32f();
+
+        let key = self.color.0.map(|c| c as f32 / 255.0);
+        // The direction of the key colour away from gray, which spill suppression removes.
+        let key_mean = (key[0] + key[1] + key[2]) / 3.0;
+        let mut tint = [key[0] - key_mean, key[1] - key_mean, key[2] - key_mean];
+        let tint_length = tint.iter().map(|c| c * c).sum::<f32>().sqrt();
+        if tint_length > 0.0 {
+            tint = tint.map(|c| c / tint_length);
+        }
+
+        for pixel in buffer.pixels_mut() {
+            let distance = ((0..3).map(|c| (pixel[c] - key[c]).powi(2)).sum::<f32>() / 3.0).sqrt();
+            let opacity = if distance <= self.tolerance {
+                0.0
+            } else if distance < self.tolerance + self.feather {
+                (distance - self.tolerance) / self.feather
+            } else {
+                1.0
+            };
+            pixel[3] *= opacity;
+
+            if self.spill > 0.0 && pixel[3] > 0.0 {
+                let mean = (pixel[0] + pixel[1] + pixel[2]) / 3.0;
+                let amount: f32 = (0..3).map(|c| (pixel[c] - mean) * tint[c]).sum();
+                if amount > 0.0 {
+                    for c in 0..3 {
+                        pixel[c] = (pixel[c] - tint[c] * amount * self.spill).clamp(0.0, 1.0);
+                    }
+                }
+            }
+        }
+
+        into_color_type(DynamicImage::ImageRgba32F(buffer), output_color(color))
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
+
+/// Get a function choosing the colour type to save an image of the given input colour type
+/// with, keeping its alpha channel, or `None` if the format cannot store transparency.
+///
+/// Each encoder only accepts some of the colour types with alpha, so the input's colour type
+/// is kept where possible and otherwise replaced with the closest one the encoder supports.
+fn alpha_color_type(format: ImageFormat) -> Option<fn(ColorType) -> ColorType> {
+    match format {
+        ImageFormat::Png | ImageFormat::Ico | ImageFormat::Avif => {
+            Some(|color| match with_alpha(color) {
+                ColorType::Rgba32F => ColorType::Rgba16,
+                color => color,
+            })
+        }
+        ImageFormat::WebP | ImageFormat::Tga | ImageFormat::Bmp => {
+            Some(|color| match with_alpha(color) {
+                ColorType::La8 => ColorType::La8,
+                _ => ColorType::Rgba8,
+            })
+        }
+        ImageFormat::Tiff => Some(|color| match with_alpha(color) {
+            ColorType::La8 | ColorType::Rgba8 => ColorType::Rgba8,
+            _ => ColorType::Rgba16,
+        }),
+        ImageFormat::Gif | ImageFormat::Qoi => Some(|_| ColorType::Rgba8),
+        ImageFormat::Farbfeld => Some(|_| ColorType::Rgba16),
+        ImageFormat::OpenExr => Some(|_| ColorType::Rgba32F),
+        _ => None,
+    }
+}
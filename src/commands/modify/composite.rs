@@ -0,0 +1,57 @@
+use image::{ImageBuffer, Luma, Rgba, Rgba32FImage};
+
+/// A single channel coverage mask, where 0.0 is uncovered and 1.0 is fully covered.
+pub type Mask = ImageBuffer<Luma<f32>, Vec<f32>>;
+
+/// Composite a layer onto a base image with its top left corner at `(x, y)`, using
+/// straight (non-premultiplied) alpha. Parts of the layer outside of the base are
+/// discarded.
+///
+/// `blend` mixes a base and layer channel value into the colour shown where both are
+/// opaque, `opacity` scales the layer's alpha and `mask` (in base coordinates)
+/// optionally scales it further per pixel.
+pub fn composite(
+    base: &mut Rgba32FImage,
+    layer: &Rgba32FImage,
+    (x, y): (i64, i64),
+    opacity: f32,
+    mask: Option<&Mask>,
+    blend: impl Fn(f32, f32) -> f32,
+) {
+    let x_range = x.max(0)..(x + layer.width() as i64).min(base.width() as i64);
+    let y_range = y.max(0)..(y + layer.height() as i64).min(base.height() as i64);
+    for by in y_range {
+        for bx in x_range.clone() {
+            let source = layer.get_pixel((bx - x) as u32, (by - y) as u32);
+            let mut source_alpha = source[3] * opacity;
+            if let Some(mask) = mask {
+                source_alpha *= mask.get_pixel(bx as u32, by as u32)[0];
+            }
+            if source_alpha <= 0.0 {
+                continue;
+            }
+
+            let backdrop = base.get_pixel_mut(bx as u32, by as u32);
+            let backdrop_alpha = backdrop[3];
+            let alpha = source_alpha + backdrop_alpha * (1.0 - source_alpha);
+            for c in 0..3 {
+                let mixed = (1.0 - backdrop_alpha) * source[c]
+                    + backdrop_alpha * blend(backdrop[c], source[c]);
+                backdrop[c] = (source_alpha * mixed
+                    + backdrop_alpha * (1.0 - source_alpha) * backdrop[c])
+                    / alpha;
+            }
+            backdrop[3] = alpha;
+        }
+    }
+}
+
+/// Paint a solid colour onto a base image through a coverage mask with its top
+/// left corner at `(x, y)`.
+pub fn paint(base: &mut Rgba32FImage, mask: &Mask, (x, y): (i64, i64), color: Rgba<u8>) {
+    let [r, g, b, a] = color.0.map(|c| c as f32 / 255.0);
+    let layer = Rgba32FImage::from_fn(mask.width(), mask.height(), |mx, my| {
+        Rgba([r, g, b, a * mask.get_pixel(mx, my)[0]])
+    });
+    composite(base, &layer, (x, y), 1.0, None, |_, source| source);
+}
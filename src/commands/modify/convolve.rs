@@ -0,0 +1,281 @@
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::into_color_type;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::{ArgGroup, Parser, ValueEnum};
+use image::{DynamicImage, ImageFormat, ImageReader};
+use rayon::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+
+/// Convolve an image with a named or custom kernel.
+///
+/// Each output channel is the weighted sum of the neighbourhood under the kernel,
+/// divided by the divisor and offset by the bias.
+#[derive(Debug, Clone, Parser)]
+#[command(group(ArgGroup::new("source").required(true)))]
+pub struct ConvolveCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// A built-in kernel to convolve the image with.
+    #[arg(long = "kernel", group = "source")]
+    pub kernel: Option<NamedKernel>,
+
+    /// A custom kernel with rows separated by ';' and values by ',' (e.g. '0,-1,0;-1,5,-1;0,-1,0').
+    #[arg(long = "matrix", group = "source", allow_hyphen_values = true)]
+    pub matrix: Option<String>,
+
+    /// A path on disk to a file containing a custom kernel, with one row per line
+    /// and values separated by commas or whitespace.
+    #[arg(long = "matrix-file", group = "source")]
+    pub matrix_path: Option<PathBuf>,
+
+    /// The value the weighted sum is divided by.
+    /// Defaults to the sum of the kernel, or 1 when the kernel sums to 0.
+    #[arg(long = "divisor", allow_hyphen_values = true)]
+    pub divisor: Option<f32>,
+
+    /// A value added to each channel after dividing, as a fraction of the
+    /// full channel range (e.g. 0.5 for mid-grey).
+    #[arg(long = "bias", default_value_t = 0.0, allow_hyphen_values = true)]
+    pub bias: f32,
+
+    /// How pixels beyond the edges of the image are sampled.
+    #[arg(long = "edge", default_value = "clamp")]
+    pub edge: EdgeMode,
+
+    /// Convolve the alpha channel as well instead of leaving it untouched.
+    #[arg(long = "include-alpha", default_value_t = false)]
+    pub include_alpha: bool,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum NamedKernel {
+    /// 3x3 average of the neighbourhood.
+    BoxBlur,
+
+    /// 3x3 approximation of a Gaussian blur.
+    Gaussian,
+
+    /// Exaggerate differences between neighbouring pixels.
+    Sharpen,
+
+    /// Make the image look raised, lit from the top left.
+    Emboss,
+
+    /// Strengthen edges while keeping the rest of the image.
+    EdgeEnhance,
+
+    /// Keep only the edges of the image (Laplacian).
+    EdgeDetect,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum EdgeMode {
+    /// Repeat the nearest edge pixel.
+    Clamp,
+
+    /// Sample from the opposite side of the image.
+    Wrap,
+
+    /// Reflect the image about its edge pixels.
+    Mirror,
+}
+
+impl EdgeMode {
+    /// Map a possibly out of bounds coordinate onto the image.
+    pub fn resolve(self, i: isize, len: usize) -> usize {
+        let len = len as isize;
+        let resolved = match self {
+            Self::Clamp => i.clamp(0, len - 1),
+            Self::Wrap => i.rem_euclid(len),
+            Self::Mirror => {
+                if len == 1 {
+                    0
+                } else {
+                    let period = 2 * (len - 1);
+                    let i = i.rem_euclid(period);
+                    if i < len { i } else { period - i }
+                }
+            }
+        };
+        resolved as usize
+    }
+}
+
+/// A matrix of weights, anchored at its centre.
+#[derive(Debug, Clone)]
+pub struct Kernel {
+    pub width: usize,
+    pub height: usize,
+    pub values: Vec<f32>,
+}
+
+impl Kernel {
+    pub fn new(rows: &[&[f32]]) -> Self {
+        Self {
+            width: rows[0].len(),
+            height: rows.len(),
+            values: rows.concat(),
+        }
+    }
+
+    /// Parse a kernel whose rows are separated by ';' or newlines and whose
+    /// values are separated by ',' or whitespace.
+    pub fn parse(text: &str) -> Result<Self> {
+        let rows = text
+            .split([';', '\n'])
+            .map(str::trim)
+            .filter(|row| !row.is_empty() && !row.starts_with('#'))
+            .map(|row| {
+                row.split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|v| !v.is_empty())
+                    .map(|v| v.parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .with_context(|| format!("invalid kernel row '{}'", row))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let Some(width) = rows.first().map(Vec::len).filter(|w| *w > 0) else {
+            bail!("the kernel must contain at least one value");
+        };
+        if rows.iter().any(|row| row.len() != width) {
+            bail!("every row of the kernel must have the same number of values");
+        }
+        Ok(Self {
+            width,
+            height: rows.len(),
+            values: rows.concat(),
+        })
+    }
+
+    pub fn sum(&self) -> f32 {
+        self.values.iter().sum()
+    }
+}
+
+impl NamedKernel {
+    /// The matrix of weights for the named kernel.
+    fn kernel(self) -> Kernel {
+        match self {
+            Self::BoxBlur => Kernel::new(&[&[1.0; 3], &[1.0; 3], &[1.0; 3]]),
+            Self::Gaussian => Kernel::new(&[&[1.0, 2.0, 1.0], &[2.0, 4.0, 2.0], &[1.0, 2.0, 1.0]]),
+            Self::Sharpen => {
+                Kernel::new(&[&[0.0, -1.0, 0.0], &[-1.0, 5.0, -1.0], &[0.0, -1.0, 0.0]])
+            }
+            Self::Emboss => Kernel::new(&[&[-2.0, -1.0, 0.0], &[-1.0, 1.0, 1.0], &[0.0, 1.0, 2.0]]),
+            Self::EdgeEnhance => Kernel::new(&[
+                &[-1.0, -1.0, -1.0],
+                &[-1.0, 10.0, -1.0],
+                &[-1.0, -1.0, -1.0],
+            ]),
+            Self::EdgeDetect => {
+                Kernel::new(&[&[-1.0, -1.0, -1.0], &[-1.0, 8.0, -1.0], &[-1.0, -1.0, -1.0]])
+            }
+        }
+    }
+}
+
+/// Convolve a single channel of `width` x `height` samples with a kernel.
+pub fn convolve_plane(
+    plane: &[f32],
+    width: usize,
+    height: usize,
+    kernel: &Kernel,
+    edge: EdgeMode,
+) -> Vec<f32> {
+    let (anchor_x, anchor_y) = ((kernel.width / 2) as isize, (kernel.height / 2) as isize);
+    let mut output = vec![0.0; plane.len()];
+    output
+        .par_chunks_mut(width)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for (x, out) in row.iter_mut().enumerate() {
+                let mut sum = 0.0;
+                for ky in 0..kernel.height {
+                    let sy = edge.resolve(y as isize + ky as isize - anchor_y, height);
+                    for kx in 0..kernel.width {
+                        let weight = kernel.values[ky * kernel.width + kx];
+                        if weight != 0.0 {
+                            let sx = edge.resolve(x as isize + kx as isize - anchor_x, width);
+                            sum += weight * plane[sy * width + sx];
+                        }
+                    }
+                }
+                *out = sum;
+            }
+        });
+    output
+}
+
+impl ExecutableCommand for ConvolveCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+
+        let kernel = if let Some(named) = self.kernel {
+            named.kernel()
+        } else if let Some(matrix) = &self.matrix {
+            Kernel::parse(matrix)?
+        } else if let Some(path) = &self.matrix_path {
+            let contents = fs::read_to_string(path).context("failed to read kernel file")?;
+            Kernel::parse(&contents)?
+        } else {
+            bail!("no kernel was specified");
+        };
+        let divisor = self.divisor.unwrap_or(match kernel.sum() {
+            0.0 => 1.0,
+            sum => sum,
+        });
+        if divisor == 0.0 {
+            bail!("the divisor must not be 0");
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        let color = image.color();
+        let mut buffer = image.into_rgba32f();
+        let (width, height) = (buffer.width() as usize, buffer.height() as usize);
+
+        let channels = if self.include_alpha { 4 } else { 3 };
+        for channel in 0..channels {
+            let plane: Vec<f32> = buffer.pixels().map(|p| p[channel]).collect();
+            let convolved = convolve_plane(&plane, width, height, &kernel, self.edge);
+            for (pixel, value) in buffer.pixels_mut().zip(convolved) {
+                pixel[channel] = value / divisor + self.bias;
+            }
+        }
+
+        into_color_type(DynamicImage::ImageRgba32F(buffer), color)
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
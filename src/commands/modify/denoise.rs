@@ -0,0 +1,259 @@
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::into_color_type;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::{DynamicImage, ImageFormat, ImageReader, Rgba32FImage};
+use rayon::prelude::*;
+use std::path::PathBuf;
+
+/// Reduce noise in an image while preserving its edges.
+///
+/// The alpha channel is left untouched.
+#[derive(Debug, Clone, Parser)]
+pub struct DenoiseCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The filter used to remove noise.
+    #[arg(long = "method", default_value = "median")]
+    pub method: DenoiseMethod,
+
+    /// The radius of the neighbourhood each pixel is filtered over (1 to 32).
+    /// Larger values remove more noise but are slower. Defaults to 1 for median
+    /// and 3 for bilateral and non-local means.
+    #[arg(long = "radius", value_parser = clap::value_parser!(u32).range(1..=32))]
+    pub radius: Option<u32>,
+
+    /// How different (0.0 to 1.0) neighbouring colours can be and still be smoothed together
+    /// by the bilateral and non-local means filters. Higher values remove more noise but
+    /// also more detail.
+    #[arg(long = "strength", default_value_t = 0.1)]
+    pub strength: f32,
+
+    /// The radius of the patches compared by the non-local means filter (1 to 8).
+    #[arg(
+        long = "patch-radius",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..=8)
+    )]
+    pub patch_radius: u32,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum DenoiseMethod {
+    /// Replace each pixel with the median of its neighbourhood; best for salt and pepper noise.
+    Median,
+
+    /// Average neighbouring pixels weighted by both distance and colour similarity.
+    Bilateral,
+
+    /// Average pixels whose surrounding patches look alike (slowest, highest quality).
+    NlMeans,
+}
+
+impl DenoiseMethod {
+    fn default_radius(self) -> u32 {
+        match self {
+            Self::Median => 1,
+            Self::Bilateral => 3,
+            Self::NlMeans => 3,
+        }
+    }
+}
+
+impl ExecutableCommand for DenoiseCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+        if !(0.0..=1.0).contains(&self.strength) || self.strength == 0.0 {
+            bail!("strength must be between 0.0 (exclusive) and 1.0");
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        let color = image.color();
+        let buffer = image.into_rgba32f();
+
+        let radius = self.radius.unwrap_or(self.method.default_radius()) as usize;
+        let filtered = match self.method {
+            DenoiseMethod::Median => median(&buffer, radius),
+            DenoiseMethod::Bilateral => bilateral(&buffer, radius, self.strength),
+            DenoiseMethod::NlMeans => {
+                nl_means(&buffer, radius, self.patch_radius as usize, self.strength)
+            }
+        };
+
+        into_color_type(DynamicImage::ImageRgba32F(filtered), color)
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
+
+/// Run `filter` for every row of the image in parallel, passing it the row
+/// index and the row's output samples. Alpha is copied from the source first.
+fn filter_rows(buffer: &Rgba32FImage, filter: impl Fn(usize, &mut [f32]) + Sync) -> Rgba32FImage {
+    let mut output = buffer.clone();
+    output
+        .par_chunks_mut(buffer.width() as usize * 4)
+        .enumerate()
+        .for_each(|(y, row)| filter(y, row));
+    output
+}
+
+/// The coordinates covered by a window of `radius` around `centre`, clamped to the image.
+fn window(centre: usize, radius: usize, len: usize) -> std::ops::RangeInclusive<usize> {
+    centre.saturating_sub(radius)..=(centre + radius).min(len - 1)
+}
+
+fn median(buffer: &Rgba32FImage, radius: usize) -> Rgba32FImage {
+    let (width, height) = (buffer.width() as usize, buffer.height() as usize);
+    let samples = buffer.as_raw();
+    filter_rows(buffer, |y, row| {
+        let mut values = Vec::with_capacity((2 * radius + 1).pow(2));
+        for x in 0..width {
+            for c in 0..3 {
+                values.clear();
+                for sy in window(y, radius, height) {
+                    for sx in window(x, radius, width) {
+                        values.push(samples[(sy * width + sx) * 4 + c]);
+                    }
+                }
+                let middle = values.len() / 2;
+                row[x * 4 + c] = *values.select_nth_unstable_by(middle, f32::total_cmp).1;
+            }
+        }
+    })
+}
+
+fn bilateral(buffer: &Rgba32FImage, radius: usize, strength: f32) -> Rgba32FImage {
+    let (width, height) = (buffer.width() as usize, buffer.height() as usize);
+    let samples = buffer.as_raw();
+    let spatial_sigma = radius as f32 / 2.0;
+    let spatial: Vec<f32> = (0..=radius)
+        .map(|d| (-((d * d) as f32) / (2.0 * spatial_sigma * spatial_sigma)).exp())
+        .collect();
+    let range_factor = -1.0 / (2.0 * strength * strength);
+
+    filter_rows(buffer, |y, row| {
+        for x in 0..width {
+            let centre = &samples[(y * width + x) * 4..][..3];
+            let mut sum = [0.0f32; 3];
+            let mut total = 0.0;
+            for sy in window(y, radius, height) {
+                let wy = spatial[sy.abs_diff(y)];
+                for sx in window(x, radius, width) {
+                    let pixel = &samples[(sy * width + sx) * 4..][..3];
+                    let distance: f32 = (0..3).map(|c| (pixel[c] - centre[c]).powi(2)).sum();
+                    let weight = wy * spatial[sx.abs_diff(x)] * (distance * range_factor).exp();
+                    for c in 0..3 {
+                        sum[c] += pixel[c] * weight;
+                    }
+                    total += weight;
+                }
+            }
+            for c in 0..3 {
+                row[x * 4 + c] = sum[c] / total;
+            }
+        }
+    })
+}
+
+fn nl_means(
+    buffer: &Rgba32FImage,
+    search_radius: usize,
+    patch_radius: usize,
+    strength: f32,
+) -> Rgba32FImage {
+    let (width, height) = (buffer.width() as usize, buffer.height() as usize);
+    let samples = buffer.as_raw();
+    let patch_area = ((2 * patch_radius + 1).pow(2) * 3) as f32;
+    let factor = -1.0 / (strength * strength * patch_area);
+
+    // Pad the colours by repeating the edges so that no patch or search
+    // offset needs bounds checking in the inner loops.
+    let pad = search_radius + patch_radius;
+    let padded_width = width + 2 * pad;
+    let padded: Vec<[f32; 3]> = (0..height + 2 * pad)
+        .flat_map(|py| {
+            let y = py.saturating_sub(pad).min(height - 1);
+            (0..padded_width).map(move |px| {
+                let x = px.saturating_sub(pad).min(width - 1);
+                let i = (y * width + x) * 4;
+                [samples[i], samples[i + 1], samples[i + 2]]
+            })
+        })
+        .collect();
+    let distance = |a: [f32; 3], b: [f32; 3]| (0..3).map(|c| (a[c] - b[c]).powi(2)).sum::<f32>();
+
+    filter_rows(buffer, |y, row| {
+        let mut sum = vec![[0.0f32; 3]; width];
+        let mut total = vec![0.0f32; width];
+        let columns = width + 2 * patch_radius;
+        let mut column_distance = vec![0.0f32; columns];
+        let centre_y = y + pad;
+
+        for neighbour_y in centre_y - search_radius..=centre_y + search_radius {
+            for dx in 0..=2 * search_radius {
+                // Sum the squared differences down each column of the patch once,
+                // then slide a window across them to get every patch distance in the row.
+                for (column, value) in column_distance.iter_mut().enumerate() {
+                    let px = search_radius + column;
+                    *value = (0..=2 * patch_radius)
+                        .map(|py| {
+                            let a = (centre_y + py - patch_radius) * padded_width + px;
+                            let b = (neighbour_y + py - patch_radius) * padded_width + px + dx
+                                - search_radius;
+                            distance(padded[a], padded[b])
+                        })
+                        .sum();
+                }
+
+                let neighbours = &padded[neighbour_y * padded_width + pad + dx - search_radius..];
+                let mut patch_distance: f32 = column_distance[..2 * patch_radius].iter().sum();
+                for x in 0..width {
+                    patch_distance += column_distance[x + 2 * patch_radius];
+                    let weight = (patch_distance * factor).exp();
+                    patch_distance -= column_distance[x];
+                    for c in 0..3 {
+                        sum[x][c] += neighbours[x][c] * weight;
+                    }
+                    total[x] += weight;
+                }
+            }
+        }
+
+        for x in 0..width {
+            for c in 0..3 {
+                row[x * 4 + c] = sum[x][c] / total[x];
+            }
+        }
+    })
+}
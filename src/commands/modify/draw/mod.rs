@@ -0,0 +1,181 @@
+pub(super) mod raster;
+mod shape;
+
+use self::raster::{fill_polygons, rasterize, stroke_polygons};
+use self::shape::{Shape, parse_shape};
+use super::composite::paint;
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::{into_color_type, layered_color, parse_color};
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use clap::Parser;
+use image::{ColorType, DynamicImage, ImageFormat, ImageReader, Rgba};
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+
+/// Draw anti-aliased shapes onto an image.
+///
+/// Shapes are given inline as '<shape>:<values>':
+///   rect:x,y,width,height
+///   rounded-rect:x,y,width,height,radius
+///   ellipse:cx,cy,rx,ry
+///   line:x1,y1,x2,y2
+///   arrow:x1,y1,x2,y2
+///   polygon:x1,y1,x2,y2,x3,y3,...
+///   path:<SVG path data>
+///
+/// or as a JSON list of objects, such as
+/// [{"shape": "rect", "x": 10, "y": 10, "width": 100, "height": 50, "stroke": "#ff0000", "stroke_width": 3, "fill": "#ff000040"}].
+/// Per-shape "stroke", "stroke_width" and "fill" override the command line style.
+#[derive(Debug, Clone, Parser)]
+#[command(verbatim_doc_comment)]
+pub struct DrawCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// A shape to draw, formatted as '<shape>:<values>'. Can be given multiple times.
+    #[arg(long = "shape", allow_hyphen_values = true, value_parser = parse_shape)]
+    pub shapes: Vec<Shape>,
+
+    /// A path on disk to a JSON file containing a list of shapes to draw.
+    /// These are drawn after any shapes given inline.
+    #[arg(long = "shapes-file")]
+    pub shapes_path: Option<PathBuf>,
+
+    /// The colour of shape outlines, formatted as hex.
+    #[arg(long = "stroke-color", default_value = "#ff0000", value_parser = parse_color)]
+    pub stroke_color: Rgba<u8>,
+
+    /// The width in pixels of shape outlines. Use 0 to draw no outline.
+    #[arg(long = "stroke-width", default_value_t = 2.0)]
+    pub stroke_width: f32,
+
+    /// The colour to fill shapes with, formatted as hex. Shapes are not filled by default.
+    #[arg(long = "fill", value_parser = parse_color)]
+    pub fill: Option<Rgba<u8>>,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+/// A shape in a JSON shapes file, along with its optional style.
+#[derive(Debug, Deserialize)]
+struct ShapeEntry {
+    #[serde(flatten)]
+    shape: Shape,
+    stroke: Option<String>,
+    stroke_width: Option<f32>,
+    fill: Option<String>,
+}
+
+/// A shape ready to be drawn with its resolved style.
+struct StyledShape {
+    shape: Shape,
+    stroke: Rgba<u8>,
+    stroke_width: f32,
+    fill: Option<Rgba<u8>>,
+}
+
+impl ExecutableCommand for DrawCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+
+        let mut shapes: Vec<StyledShape> = self
+            .shapes
+            .iter()
+            .map(|shape| StyledShape {
+                shape: shape.clone(),
+                stroke: self.stroke_color,
+                stroke_width: self.stroke_width,
+                fill: self.fill,
+            })
+            .collect();
+        if let Some(path) = &self.shapes_path {
+            let contents = fs::read_to_string(path).context("failed to read shapes file")?;
+            let entries: Vec<ShapeEntry> =
+                serde_json::from_str(&contents).context("failed to parse shapes file")?;
+            for entry in entries {
+                let color = |value: Option<String>, default| match value {
+                    Some(value) => parse_color(&value).map_err(|e| anyhow!(e)),
+                    None => Ok(default),
+                };
+                shapes.push(StyledShape {
+                    shape: entry.shape,
+                    stroke: color(entry.stroke, self.stroke_color)?,
+                    stroke_width: entry.stroke_width.unwrap_or(self.stroke_width),
+                    fill: entry
+                        .fill
+                        .map(|fill| parse_color(&fill).map_err(|e| anyhow!(e)))
+                        .transpose()?
+                        .or(self.fill),
+                });
+            }
+        }
+        if shapes.is_empty() {
+            bail!("no shapes were given to draw");
+        }
+        if shapes.iter().any(|s| s.stroke_width < 0.0) {
+            bail!("stroke width must not be negative");
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+
+        let uses_color = shapes
+            .iter()
+            .flat_map(|s| [Some(s.stroke), s.fill])
+            .flatten()
+            .any(|c| c[0] != c[1] || c[1] != c[2]);
+        let color = if uses_color {
+            layered_color(image.color(), ColorType::Rgba8)
+        } else {
+            image.color()
+        };
+        let mut buffer = image.into_rgba32f();
+        let bounds = buffer.dimensions();
+
+        for styled in &shapes {
+            if let Some(fill) = styled.fill.filter(|_| styled.shape.is_fillable()) {
+                if let Some((mask, origin)) = rasterize(&fill_polygons(&styled.shape)?, bounds) {
+                    paint(&mut buffer, &mask, origin, fill);
+                }
+            }
+            if styled.stroke_width > 0.0 {
+                let polygons = stroke_polygons(&styled.shape, styled.stroke_width)?;
+                if let Some((mask, origin)) = rasterize(&polygons, bounds) {
+                    paint(&mut buffer, &mask, origin, styled.stroke);
+                }
+            }
+        }
+
+        into_color_type(DynamicImage::ImageRgba32F(buffer), color)
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
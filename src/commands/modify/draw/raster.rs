@@ -0,0 +1,175 @@
+use super::shape::{Contour, Shape, distance, round_steps};
+use crate::commands::modify::composite::Mask;
+use ab_glyph_rasterizer::{Point, Rasterizer, point};
+use anyhow::Result;
+use image::Luma;
+use std::f32::consts::PI;
+
+/// Rasterise closed polygons into an anti-aliased coverage mask clipped to an image of
+/// the given size, returning the mask and the position of its top left corner.
+/// Overlapping polygons with the same winding are merged, while opposite winding cuts holes.
+pub fn rasterize(
+    polygons: &[Vec<Point>],
+    (width, height): (u32, u32),
+) -> Option<(Mask, (i64, i64))> {
+    let points = polygons.iter().flatten();
+    let (min_x, min_y, max_x, max_y) = points.fold(
+        (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+        |(min_x, min_y, max_x, max_y), p| {
+            (
+                min_x.min(p.x),
+                min_y.min(p.y),
+                max_x.max(p.x),
+                max_y.max(p.y),
+            )
+        },
+    );
+    let x0 = (min_x.floor() as i64).max(0);
+    let y0 = (min_y.floor() as i64).max(0);
+    let x1 = (max_x.ceil() as i64).min(width as i64);
+    let y1 = (max_y.ceil() as i64).min(height as i64);
+    if x1 <= x0 || y1 <= y0 {
+        return None;
+    }
+
+    let (mask_width, mask_height) = ((x1 - x0) as usize, (y1 - y0) as usize);
+    let mut rasterizer = Rasterizer::new(mask_width, mask_height);
+    for polygon in polygons {
+        for (i, start) in polygon.iter().enumerate() {
+            let end = polygon[(i + 1) % polygon.len()];
+            let start = point(start.x - x0 as f32, start.y - y0 as f32);
+            let end = point(end.x - x0 as f32, end.y - y0 as f32);
+            draw_clipped_line(&mut rasterizer, start, end, mask_width as f32);
+        }
+    }
+
+    let mut mask = Mask::new(mask_width as u32, mask_height as u32);
+    rasterizer.for_each_pixel_2d(|x, y, coverage| {
+        mask.put_pixel(x, y, Luma([coverage.min(1.0)]));
+    });
+    Some((mask, (x0, y0)))
+}
+
+/// The rasteriser accumulates coverage along each row, so parts of lines to the left or
+/// right of it must be moved onto its edges rather than dropped to keep rows balanced.
+fn draw_clipped_line(rasterizer: &mut Rasterizer, start: Point, end: Point, width: f32) {
+    let mut splits = vec![0.0, 1.0];
+    if start.x != end.x {
+        for edge in [0.0, width] {
+            let t = (edge - start.x) / (end.x - start.x);
+            if t > 0.0 && t < 1.0 {
+                splits.push(t);
+            }
+        }
+    }
+    splits.sort_by(f32::total_cmp);
+
+    let at = |t: f32| {
+        point(
+            (start.x + (end.x - start.x) * t).clamp(0.0, width),
+            start.y + (end.y - start.y) * t,
+        )
+    };
+    for pair in splits.windows(2) {
+        rasterizer.draw_line(at(pair[0]), at(pair[1]));
+    }
+}
+
+/// Give a polygon a consistent winding, clockwise or anticlockwise.
+fn wound(mut polygon: Vec<Point>, clockwise: bool) -> Vec<Point> {
+    let area: f32 = polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum();
+    if (area > 0.0) != clockwise {
+        polygon.reverse();
+    }
+    polygon
+}
+
+/// The polygons covering the inside of a shape.
+pub fn fill_polygons(shape: &Shape) -> Result<Vec<Vec<Point>>> {
+    Ok(shape
+        .contours()?
+        .into_iter()
+        .map(|contour| contour.points)
+        .filter(|points| points.len() > 2)
+        .collect())
+}
+
+/// The polygons covering the outline of a shape drawn with the given stroke width.
+pub fn stroke_polygons(shape: &Shape, width: f32) -> Result<Vec<Vec<Point>>> {
+    let half = width / 2.0;
+
+    // Rectangles and ellipses are stroked exactly as the area between their
+    // grown and shrunk outlines, keeping their corners sharp.
+    if let Some(outer) = shape.grown(half)? {
+        let mut polygons = vec![wound(outer, true)];
+        polygons.extend(shape.grown(-half)?.map(|inner| wound(inner, false)));
+        return Ok(polygons);
+    }
+
+    if let Shape::Arrow { x1, y1, .. } = shape {
+        let [tip, base, left, right] = shape.arrow_head(width);
+        let shaft = Contour {
+            points: vec![point(*x1, *y1), base],
+            closed: false,
+        };
+        let mut polygons = stroke_contour(&shaft, half)?;
+        polygons.push(wound(vec![tip, left, right], true));
+        return Ok(polygons);
+    }
+
+    let mut polygons = Vec::new();
+    for contour in shape.contours()? {
+        polygons.extend(stroke_contour(&contour, half)?);
+    }
+    Ok(polygons)
+}
+
+/// Stroke a contour as a quad along each segment with round joins and caps.
+fn stroke_contour(contour: &Contour, half: f32) -> Result<Vec<Vec<Point>>> {
+    let points = &contour.points;
+    let segments = if contour.closed {
+        points.len()
+    } else {
+        points.len() - 1
+    };
+
+    let mut polygons = Vec::new();
+    for i in 0..segments {
+        let (start, end) = (points[i], points[(i + 1) % points.len()]);
+        let length = distance(start, end);
+        if length <= f32::EPSILON {
+            continue;
+        }
+        let (nx, ny) = (
+            -(end.y - start.y) / length * half,
+            (end.x - start.x) / length * half,
+        );
+        polygons.push(wound(
+            vec![
+                point(start.x + nx, start.y + ny),
+                point(end.x + nx, end.y + ny),
+                point(end.x - nx, end.y - ny),
+                point(start.x - nx, start.y - ny),
+            ],
+            true,
+        ));
+    }
+
+    let steps = round_steps(PI * half * 2.0, 8)?;
+    for centre in points {
+        polygons.push(wound(
+            (0..steps)
+                .map(|i| {
+                    let angle = 2.0 * PI * i as f32 / steps as f32;
+                    point(centre.x + half * angle.cos(), centre.y + half * angle.sin())
+                })
+                .collect(),
+            true,
+        ));
+    }
+    Ok(polygons)
+}
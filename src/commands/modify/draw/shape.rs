@@ -0,0 +1,549 @@
+use ab_glyph_rasterizer::{Point, point};
+use anyhow::{Result, bail};
+use serde::Deserialize;
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// A shape that can be drawn onto an image, in pixel coordinates.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "shape", rename_all = "kebab-case")]
+pub enum Shape {
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    RoundedRect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        radius: f32,
+    },
+    Ellipse {
+        cx: f32,
+        cy: f32,
+        rx: f32,
+        ry: f32,
+    },
+    Line {
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+    },
+    Arrow {
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+    },
+    Polygon {
+        points: Vec<[f32; 2]>,
+    },
+    Path {
+        d: String,
+    },
+}
+
+/// A sequence of connected points, optionally joining back to its start.
+#[derive(Debug, Clone)]
+pub struct Contour {
+    pub points: Vec<Point>,
+    pub closed: bool,
+}
+
+/// Parse an inline shape formatted as '<shape>:<values>', such as 'rect:x,y,width,height'
+/// or 'path:M 10 10 L 50 50'.
+pub fn parse_shape(value: &str) -> Result<Shape, String> {
+    let Some((kind, values)) = value.split_once(':') else {
+        return Err("expected a shape formatted as '<shape>:<values>'".to_string());
+    };
+    if kind == "path" {
+        return Ok(Shape::Path {
+            d: values.to_string(),
+        });
+    }
+
+    let numbers = values
+        .split(',')
+        .map(|v| v.trim().parse::<f32>().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let shape = match (kind, numbers.as_slice()) {
+        ("rect", &[x, y, width, height]) => Shape::Rect {
+            x,
+            y,
+            width,
+            height,
+        },
+        ("rounded-rect", &[x, y, width, height, radius]) => Shape::RoundedRect {
+            x,
+            y,
+            width,
+            height,
+            radius,
+        },
+        ("ellipse", &[cx, cy, rx, ry]) => Shape::Ellipse { cx, cy, rx, ry },
+        ("line", &[x1, y1, x2, y2]) => Shape::Line { x1, y1, x2, y2 },
+        ("arrow", &[x1, y1, x2, y2]) => Shape::Arrow { x1, y1, x2, y2 },
+        ("polygon", points) if points.len() >= 6 && points.len() % 2 == 0 => Shape::Polygon {
+            points: points.chunks_exact(2).map(|p| [p[0], p[1]]).collect(),
+        },
+        ("rect", _) => return Err("expected 'rect:x,y,width,height'".to_string()),
+        ("rounded-rect", _) => {
+            return Err("expected 'rounded-rect:x,y,width,height,radius'".to_string());
+        }
+        ("ellipse", _) => return Err("expected 'ellipse:cx,cy,rx,ry'".to_string()),
+        ("line" | "arrow", _) => return Err(format!("expected '{kind}:x1,y1,x2,y2'")),
+        ("polygon", _) => {
+            return Err("expected 'polygon:x1,y1,x2,y2,x3,y3,...' with at least 3 points".into());
+        }
+        _ => {
+            return Err(format!(
+                "unknown shape '{kind}', expected one of rect, rounded-rect, ellipse, line, arrow, polygon or path"
+            ));
+        }
+    };
+    Ok(shape)
+}
+
+impl Shape {
+    /// Whether the shape encloses an area that can be filled.
+    pub fn is_fillable(&self) -> bool {
+        !matches!(self, Self::Line { .. } | Self::Arrow { .. })
+    }
+
+    /// The outline of the shape as contours, with curves flattened into line segments.
+    pub fn contours(&self) -> Result<Vec<Contour>> {
+        let closed = |points| {
+            vec![Contour {
+                points,
+                closed: true,
+            }]
+        };
+        Ok(match self {
+            Self::Rect { .. } | Self::RoundedRect { .. } | Self::Ellipse { .. } => {
+                closed(self.grown(0.0)?.unwrap_or_default())
+            }
+            Self::Line { x1, y1, x2, y2 } | Self::Arrow { x1, y1, x2, y2 } => vec![Contour {
+                points: vec![point(*x1, *y1), point(*x2, *y2)],
+                closed: false,
+            }],
+            Self::Polygon { points } => closed(points.iter().map(|p| point(p[0], p[1])).collect()),
+            Self::Path { d } => parse_path(d)?,
+        })
+    }
+
+    /// The outline of a rectangle, rounded rectangle or ellipse grown outwards by
+    /// `amount` (or shrunk when negative), which allows their strokes to keep sharp
+    /// corners. Returns `None` for other shapes or if the shape shrinks away entirely.
+    pub fn grown(&self, amount: f32) -> Result<Option<Vec<Point>>> {
+        Ok(match *self {
+            Self::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let (x0, y0) = (x - amount, y - amount);
+                let (x1, y1) = (x + width + amount, y + height + amount);
+                (x1 > x0 && y1 > y0)
+                    .then(|| vec![point(x0, y0), point(x1, y0), point(x1, y1), point(x0, y1)])
+            }
+            Self::RoundedRect {
+                x,
+                y,
+                width,
+                height,
+                radius,
+            } => {
+                let radius = radius.min(width / 2.0).min(height / 2.0).max(0.0);
+                let (x0, y0) = (x - amount, y - amount);
+                let (x1, y1) = (x + width + amount, y + height + amount);
+                if x1 <= x0 || y1 <= y0 {
+                    return Ok(None);
+                }
+                let radius = (radius + amount).clamp(0.0, (x1 - x0).min(y1 - y0) / 2.0);
+                let corners = [
+                    (x1 - radius, y0 + radius, -FRAC_PI_2),
+                    (x1 - radius, y1 - radius, 0.0),
+                    (x0 + radius, y1 - radius, FRAC_PI_2),
+                    (x0 + radius, y0 + radius, PI),
+                ];
+                let steps = round_steps(FRAC_PI_2 * radius, 1)?;
+                Some(
+                    corners
+                        .iter()
+                        .flat_map(|(cx, cy, start)| {
+                            (0..=steps).map(move |i| {
+                                let angle = start + FRAC_PI_2 * i as f32 / steps as f32;
+                                point(cx + radius * angle.cos(), cy + radius * angle.sin())
+                            })
+                        })
+                        .collect(),
+                )
+            }
+            Self::Ellipse { cx, cy, rx, ry } => {
+                let (rx, ry) = (rx + amount, ry + amount);
+                if rx <= 0.0 || ry <= 0.0 {
+                    return Ok(None);
+                }
+                let steps = round_steps(PI * (rx + ry), 16)?;
+                Some(
+                    (0..steps)
+                        .map(|i| {
+                            let angle = 2.0 * PI * i as f32 / steps as f32;
+                            point(cx + rx * angle.cos(), cy + ry * angle.sin())
+                        })
+                        .collect(),
+                )
+            }
+            _ => None,
+        })
+    }
+
+    /// The triangle forming the head of an arrow drawn with the given stroke width,
+    /// as its tip followed by the point where the shaft meets it and the two barbs.
+    /// Only meaningful for arrows.
+    pub fn arrow_head(&self, stroke_width: f32) -> [Point; 4] {
+        let Self::Arrow { x1, y1, x2, y2 } = *self else {
+            return [point(0.0, 0.0); 4];
+        };
+        let length = (x2 - x1).hypot(y2 - y1).max(f32::EPSILON);
+        let (dx, dy) = ((x2 - x1) / length, (y2 - y1) / length);
+        let head = (stroke_width * 4.0).max(8.0).min(length);
+        let (bx, by) = (x2 - dx * head, y2 - dy * head);
+        let half = head / 2.0;
+        [
+            point(x2, y2),
+            point(bx, by),
+            point(bx - dy * half, by + dx * half),
+            point(bx + dy * half, by - dx * half),
+        ]
+    }
+}
+
+/// Parse SVG path data into contours.
+fn parse_path(data: &str) -> Result<Vec<Contour>> {
+    let tokens = tokenize_path(data)?;
+    let mut contours: Vec<Contour> = Vec::new();
+    let mut current = point(0.0, 0.0);
+    let mut start = current;
+    // The last control point of the previous curve, for the smooth curve commands.
+    let mut last_control: Option<(char, Point)> = None;
+    let mut command = None;
+    let mut index = 0;
+
+    let numbers = |index: &mut usize, count: usize| -> Result<Vec<f32>> {
+        let values = tokens[*index..]
+            .iter()
+            .take(count)
+            .map_while(|t| match t {
+                PathToken::Number(n) => Some(*n),
+                PathToken::Command(_) => None,
+            })
+            .collect::<Vec<_>>();
+        if values.len() != count {
+            bail!("path data is missing a number");
+        }
+        *index += count;
+        Ok(values)
+    };
+
+    while index < tokens.len() {
+        if let PathToken::Command(c) = tokens[index] {
+            command = Some(c);
+            index += 1;
+        }
+        let Some(c) = command else {
+            bail!("path data must start with a command");
+        };
+        let relative = c.is_ascii_lowercase();
+        let offset = |p: Point, current: Point| {
+            if relative {
+                point(p.x + current.x, p.y + current.y)
+            } else {
+                p
+            }
+        };
+
+        match c.to_ascii_uppercase() {
+            'M' => {
+                let v = numbers(&mut index, 2)?;
+                current = offset(point(v[0], v[1]), current);
+                start = current;
+                contours.push(Contour {
+                    points: vec![current],
+                    closed: false,
+                });
+                // Further coordinate pairs after a move are treated as lines.
+                command = Some(if relative { 'l' } else { 'L' });
+                last_control = None;
+                continue;
+            }
+            'L' => {
+                let v = numbers(&mut index, 2)?;
+                let end = offset(point(v[0], v[1]), current);
+                extend(&mut contours, current, vec![end]);
+                current = end;
+            }
+            'H' => {
+                let v = numbers(&mut index, 1)?;
+                let x = if relative { current.x + v[0] } else { v[0] };
+                extend(&mut contours, current, vec![point(x, current.y)]);
+                current.x = x;
+            }
+            'V' => {
+                let v = numbers(&mut index, 1)?;
+                let y = if relative { current.y + v[0] } else { v[0] };
+                extend(&mut contours, current, vec![point(current.x, y)]);
+                current.y = y;
+            }
+            'C' | 'S' => {
+                let smooth = c.eq_ignore_ascii_case(&'S');
+                let v = numbers(&mut index, if smooth { 4 } else { 6 })?;
+                let first = match (smooth, last_control) {
+                    (false, _) => offset(point(v[0], v[1]), current),
+                    (true, Some(('C', control))) => {
+                        point(2.0 * current.x - control.x, 2.0 * current.y - control.y)
+                    }
+                    (true, _) => current,
+                };
+                let rest = if smooth { &v[..] } else { &v[2..] };
+                let second = offset(point(rest[0], rest[1]), current);
+                let end = offset(point(rest[2], rest[3]), current);
+                extend(
+                    &mut contours,
+                    current,
+                    flatten_cubic(current, first, second, end),
+                );
+                current = end;
+                last_control = Some(('C', second));
+                continue;
+            }
+            'Q' | 'T' => {
+                let smooth = c.eq_ignore_ascii_case(&'T');
+                let v = numbers(&mut index, if smooth { 2 } else { 4 })?;
+                let control = match (smooth, last_control) {
+                    (false, _) => offset(point(v[0], v[1]), current),
+                    (true, Some(('Q', control))) => {
+                        point(2.0 * current.x - control.x, 2.0 * current.y - control.y)
+                    }
+                    (true, _) => current,
+                };
+                let rest = if smooth { &v[..] } else { &v[2..] };
+                let end = offset(point(rest[0], rest[1]), current);
+                extend(&mut contours, current, flatten_quad(current, control, end));
+                current = end;
+                last_control = Some(('Q', control));
+                continue;
+            }
+            'Z' => {
+                if let Some(contour) = contours.last_mut() {
+                    contour.closed = true;
+                }
+                current = start;
+                // A new subpath starts at the same point if drawing continues.
+                contours.push(Contour {
+                    points: vec![current],
+                    closed: false,
+                });
+                command = None;
+            }
+            'A' => {
+                let v = numbers(&mut index, 7)?;
+                if [v[3], v[4]].iter().any(|flag| *flag != 0.0 && *flag != 1.0) {
+                    bail!("arc flags must be 0 or 1");
+                }
+                let end = offset(point(v[5], v[6]), current);
+                let arc = flatten_arc(current, (v[0], v[1]), v[2], v[3] != 0.0, v[4] != 0.0, end);
+                extend(&mut contours, current, arc);
+                current = end;
+            }
+            other => bail!("unknown path command '{other}'"),
+        }
+        last_control = None;
+    }
+
+    contours.retain(|contour| contour.points.len() > 1);
+    if contours.is_empty() {
+        bail!("the path does not contain any segments");
+    }
+    Ok(contours)
+}
+
+/// Add points to the current subpath, starting a new one at `from` if there is none.
+fn extend(contours: &mut Vec<Contour>, from: Point, points: Vec<Point>) {
+    match contours.last_mut() {
+        Some(contour) => contour.points.extend(points),
+        None => contours.push(Contour {
+            points: [vec![from], points].concat(),
+            closed: false,
+        }),
+    }
+}
+
+enum PathToken {
+    Command(char),
+    Number(f32),
+}
+
+fn tokenize_path(data: &str) -> Result<Vec<PathToken>> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = data.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() || c == ',' {
+            i += 1;
+        } else if c.is_ascii_alphabetic() && !matches!(c, 'e' | 'E') {
+            tokens.push(PathToken::Command(c));
+            i += 1;
+        } else {
+            // Numbers may run together, such as '10-5' or '.5.5'.
+            let start = i;
+            let mut seen_dot = false;
+            let mut seen_exponent = false;
+            if matches!(chars[i], '+' | '-') {
+                i += 1;
+            }
+            while i < chars.len() {
+                match chars[i] {
+                    '0'..='9' => {}
+                    '.' if !seen_dot && !seen_exponent => seen_dot = true,
+                    'e' | 'E' if !seen_exponent => {
+                        seen_exponent = true;
+                        if matches!(chars.get(i + 1), Some('+' | '-')) {
+                            i += 1;
+                        }
+                    }
+                    _ => break,
+                }
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let number = text
+                .parse::<f32>()
+                .map_err(|_| anyhow::anyhow!("invalid number '{text}' in path data"))?;
+            tokens.push(PathToken::Number(number));
+        }
+    }
+    Ok(tokens)
+}
+
+/// The most line segments the outline of a round shape may be split into, so that huge
+/// shapes return an error rather than running out of memory.
+const MAX_ROUND_STEPS: usize = 1 << 18;
+
+/// The number of line segments used to approximate a round outline with the given
+/// length, one per pixel but no fewer than `min`.
+pub fn round_steps(length: f32, min: usize) -> Result<usize> {
+    let steps = length.ceil();
+    if steps.is_nan() || steps > MAX_ROUND_STEPS as f32 {
+        bail!("the shape is too large to draw");
+    }
+    Ok((steps as usize).max(min))
+}
+
+/// The number of line segments used to approximate a curve with the given control polygon length.
+fn curve_steps(length: f32) -> usize {
+    ((length / 2.0).ceil() as usize).clamp(4, 128)
+}
+
+pub fn distance(a: Point, b: Point) -> f32 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
+
+fn flatten_quad(p0: Point, p1: Point, p2: Point) -> Vec<Point> {
+    let steps = curve_steps(distance(p0, p1) + distance(p1, p2));
+    (1..=steps)
+        .map(|i| {
+            let t = i as f32 / steps as f32;
+            let u = 1.0 - t;
+            point(
+                u * u * p0.x + 2.0 * u * t * p1.x + t * t * p2.x,
+                u * u * p0.y + 2.0 * u * t * p1.y + t * t * p2.y,
+            )
+        })
+        .collect()
+}
+
+fn flatten_cubic(p0: Point, p1: Point, p2: Point, p3: Point) -> Vec<Point> {
+    let steps = curve_steps(distance(p0, p1) + distance(p1, p2) + distance(p2, p3));
+    (1..=steps)
+        .map(|i| {
+            let t = i as f32 / steps as f32;
+            let u = 1.0 - t;
+            let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+            point(
+                a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+                a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+            )
+        })
+        .collect()
+}
+
+/// Flatten an SVG elliptical arc from `p0` to `p1`, converting its endpoint parameters
+/// to a centre and angles as described in the SVG specification's implementation notes.
+fn flatten_arc(
+    p0: Point,
+    (rx, ry): (f32, f32),
+    rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    p1: Point,
+) -> Vec<Point> {
+    // Arcs between the same point are skipped and arcs with a zero radius are straight lines.
+    if p0 == p1 {
+        return Vec::new();
+    }
+    if rx == 0.0 || ry == 0.0 {
+        return vec![p1];
+    }
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+
+    // Move the midpoint of the chord to the origin and undo the ellipse's rotation.
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let (dx, dy) = ((p0.x - p1.x) / 2.0, (p0.y - p1.y) / 2.0);
+    let (x, y) = (cos * dx + sin * dy, -sin * dx + cos * dy);
+
+    // Scale the radii up if they are too small to reach between the endpoints.
+    let scale = (x * x) / (rx * rx) + (y * y) / (ry * ry);
+    if scale > 1.0 {
+        rx *= scale.sqrt();
+        ry *= scale.sqrt();
+    }
+
+    let numerator = rx * rx * ry * ry - rx * rx * y * y - ry * ry * x * x;
+    let denominator = rx * rx * y * y + ry * ry * x * x;
+    let sign = if large_arc == sweep { -1.0 } else { 1.0 };
+    let coefficient = sign * (numerator / denominator).max(0.0).sqrt();
+    let (cx, cy) = (coefficient * rx * y / ry, -coefficient * ry * x / rx);
+    let centre = point(
+        cos * cx - sin * cy + (p0.x + p1.x) / 2.0,
+        sin * cx + cos * cy + (p0.y + p1.y) / 2.0,
+    );
+
+    let start = ((y - cy) / ry).atan2((x - cx) / rx);
+    let end = ((-y - cy) / ry).atan2((-x - cx) / rx);
+    let mut sweep_angle = end - start;
+    if sweep && sweep_angle < 0.0 {
+        sweep_angle += 2.0 * PI;
+    } else if !sweep && sweep_angle > 0.0 {
+        sweep_angle -= 2.0 * PI;
+    }
+
+    let steps = curve_steps(sweep_angle.abs() * rx.max(ry));
+    (1..=steps)
+        .map(|i| {
+            if i == steps {
+                return p1;
+            }
+            let (sin_t, cos_t) = (start + sweep_angle * i as f32 / steps as f32).sin_cos();
+            point(
+                centre.x + rx * cos_t * cos - ry * sin_t * sin,
+                centre.y + rx * cos_t * sin + ry * sin_t * cos,
+            )
+        })
+        .collect()
+}
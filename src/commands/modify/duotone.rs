@@ -0,0 +1,63 @@
+use super::effects;
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::parse_color;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use image::{ImageFormat, ImageReader, Rgba};
+use std::path::PathBuf;
+
+/// Recolour an image using a gradient between a shadow and a highlight colour.
+#[derive(Debug, Clone, Parser)]
+pub struct DuotoneCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The hex colour used for the darkest parts of the image (e.g. '#1b1f3a').
+    #[arg(long = "shadow", value_parser = parse_color)]
+    pub shadow: Rgba<u8>,
+
+    /// The hex colour used for the brightest parts of the image (e.g. '#ffb3a7').
+    #[arg(long = "highlight", value_parser = parse_color)]
+    pub highlight: Rgba<u8>,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+impl ExecutableCommand for DuotoneCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        effects::duotone(image, self.shadow, self.highlight)
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+use super::convolve::{EdgeMode, Kernel, convolve_plane};
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::into_color_type;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::{ColorType, DynamicImage, ImageBuffer, ImageFormat, ImageReader, Luma};
+use std::path::PathBuf;
+
+/// Detect the edges of an image.
+///
+/// Gradient operators output the gradient magnitude as a grayscale image,
+/// while Canny (or any operator given a threshold) outputs a binary edge map.
+#[derive(Debug, Clone, Parser)]
+pub struct EdgesCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The edge detection operator to use.
+    #[arg(long = "operator", default_value = "sobel")]
+    pub operator: EdgeOperator,
+
+    /// The sigma of the Gaussian blur applied before detecting edges to reduce noise.
+    /// Defaults to 1.4 for Canny and no blur for the gradient operators.
+    #[arg(long = "sigma")]
+    pub sigma: Option<f32>,
+
+    /// The gradient strength (0.0 to 1.0) below which Canny discards edges.
+    #[arg(long = "low", default_value_t = 0.1)]
+    pub low_threshold: f32,
+
+    /// The gradient strength (0.0 to 1.0) above which Canny always keeps edges.
+    /// Weaker edges are only kept when connected to one of these.
+    #[arg(long = "high", default_value_t = 0.2)]
+    pub high_threshold: f32,
+
+    /// Output a binary edge map of gradient strengths (0.0 to 1.0) at or above this
+    /// value instead of the gradient magnitude. Ignored by Canny.
+    #[arg(long = "threshold")]
+    pub threshold: Option<f32>,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum EdgeOperator {
+    /// 3x3 Sobel gradient magnitude.
+    Sobel,
+
+    /// 3x3 Prewitt gradient magnitude.
+    Prewitt,
+
+    /// 3x3 Scharr gradient magnitude, more rotationally accurate than Sobel.
+    Scharr,
+
+    /// Canny edge detector with non-maximum suppression and hysteresis thresholding.
+    Canny,
+}
+
+impl EdgeOperator {
+    /// The horizontal gradient kernel; the vertical kernel is its transpose.
+    fn kernel(self) -> Kernel {
+        let (edge, centre) = match self {
+            Self::Sobel | Self::Canny => (1.0, 2.0),
+            Self::Prewitt => (1.0, 1.0),
+            Self::Scharr => (3.0, 10.0),
+        };
+        Kernel::new(&[
+            &[-edge, 0.0, edge],
+            &[-centre, 0.0, centre],
+            &[-edge, 0.0, edge],
+        ])
+    }
+}
+
+fn transpose(kernel: &Kernel) -> Kernel {
+    Kernel {
+        width: kernel.height,
+        height: kernel.width,
+        values: (0..kernel.width)
+            .flat_map(|x| (0..kernel.height).map(move |y| (x, y)))
+            .map(|(x, y)| kernel.values[y * kernel.width + x])
+            .collect(),
+    }
+}
+
+/// Blur a plane with a separable Gaussian kernel.
+pub fn gaussian_blur(plane: &[f32], width: usize, height: usize, sigma: f32) -> Vec<f32> {
+    let radius = (sigma * 3.0).ceil() as isize;
+    let weights: Vec<f32> = (-radius..=radius)
+        .map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let sum: f32 = weights.iter().sum();
+    let horizontal = Kernel {
+        width: weights.len(),
+        height: 1,
+        values: weights.iter().map(|w| w / sum).collect(),
+    };
+    let vertical = transpose(&horizontal);
+    let blurred = convolve_plane(plane, width, height, &horizontal, EdgeMode::Mirror);
+    convolve_plane(&blurred, width, height, &vertical, EdgeMode::Mirror)
+}
+
+impl ExecutableCommand for EdgesCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+        for value in [self.low_threshold, self.high_threshold]
+            .into_iter()
+            .chain(self.threshold)
+        {
+            if !(0.0..=1.0).contains(&value) {
+                bail!("thresholds must be between 0.0 and 1.0");
+            }
+        }
+        if self.low_threshold > self.high_threshold {
+            bail!("the low threshold must not be greater than the high threshold");
+        }
+        let sigma = self.sigma.unwrap_or(match self.operator {
+            EdgeOperator::Canny => 1.4,
+            _ => 0.0,
+        });
+        if sigma < 0.0 {
+            bail!("sigma must not be negative");
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        let color = image.color();
+        let luma = image.to_luma32f();
+        let (width, height) = (luma.width() as usize, luma.height() as usize);
+
+        let mut plane = luma.into_raw();
+        if sigma > 0.0 {
+            plane = gaussian_blur(&plane, width, height, sigma);
+        }
+
+        // Normalise so that a full black to white step has a strength of 1.0.
+        let kernel_x = self.operator.kernel();
+        let kernel_y = transpose(&kernel_x);
+        let scale: f32 = kernel_x.values.iter().filter(|v| **v > 0.0).sum();
+        let gx = convolve_plane(&plane, width, height, &kernel_x, EdgeMode::Clamp);
+        let gy = convolve_plane(&plane, width, height, &kernel_y, EdgeMode::Clamp);
+        let magnitude: Vec<f32> = gx
+            .iter()
+            .zip(&gy)
+            .map(|(x, y)| x.hypot(*y) / scale)
+            .collect();
+
+        let (output, output_color) = match (self.operator, self.threshold) {
+            (EdgeOperator::Canny, _) => (
+                canny(
+                    &magnitude,
+                    &gx,
+                    &gy,
+                    width,
+                    height,
+                    self.low_threshold,
+                    self.high_threshold,
+                ),
+                ColorType::L8,
+            ),
+            (_, Some(threshold)) => (
+                magnitude
+                    .iter()
+                    .map(|m| if *m >= threshold { 1.0 } else { 0.0 })
+                    .collect(),
+                ColorType::L8,
+            ),
+            // Keep the precision of high bit depth inputs in the magnitude image.
+            (_, None) if color.bytes_per_pixel() > color.channel_count() => {
+                (magnitude, ColorType::L16)
+            }
+            (_, None) => (magnitude, ColorType::L8),
+        };
+
+        let buffer: ImageBuffer<Luma<f32>, Vec<f32>> =
+            ImageBuffer::from_raw(width as u32, height as u32, output)
+                .context("edge map does not match the image dimensions")?;
+        into_color_type(
+            DynamicImage::ImageRgba32F(DynamicImage::from(buffer).into_rgba32f()),
+            output_color,
+        )
+        .save_with_format(self.output_path, output_format)
+        .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
+
+/// Thin the gradient magnitude to single pixel wide edges and keep those that
+/// pass the high threshold or connect to an edge that does.
+fn canny(
+    magnitude: &[f32],
+    gx: &[f32],
+    gy: &[f32],
+    width: usize,
+    height: usize,
+    low: f32,
+    high: f32,
+) -> Vec<f32> {
+    const WEAK: u8 = 1;
+    const STRONG: u8 = 2;
+
+    let mut state = vec![0u8; magnitude.len()];
+    let mut strong = Vec::new();
+    for y in 1..height.saturating_sub(1) {
+        for x in 1..width.saturating_sub(1) {
+            let i = y * width + x;
+            let m = magnitude[i];
+            if m < low {
+                continue;
+            }
+
+            // Compare against the neighbours along the gradient direction,
+            // quantised to one of four orientations.
+            let angle = gy[i].atan2(gx[i]).to_degrees().rem_euclid(180.0);
+            let (a, b) = if !(22.5..157.5).contains(&angle) {
+                (i - 1, i + 1)
+            } else if angle < 67.5 {
+                (i - width - 1, i + width + 1)
+            } else if angle < 112.5 {
+                (i - width, i + width)
+            } else {
+                (i - width + 1, i + width - 1)
+            };
+            if m < magnitude[a] || m < magnitude[b] {
+                continue;
+            }
+
+            if m >= high {
+                state[i] = STRONG;
+                strong.push(i);
+            } else {
+                state[i] = WEAK;
+            }
+        }
+    }
+
+    while let Some(i) = strong.pop() {
+        let (x, y) = (i % width, i / width);
+        for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+            for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+                let n = ny * width + nx;
+                if state[n] == WEAK {
+                    state[n] = STRONG;
+                    strong.push(n);
+                }
+            }
+        }
+    }
+
+    state
+        .into_iter()
+        .map(|s| if s == STRONG { 1.0 } else { 0.0 })
+        .collect()
+}
@@ -0,0 +1,66 @@
+use crate::commands::helpers::{color_equivalent, into_color_type};
+use image::{DynamicImage, Rgba};
+
+/// Rec. 709 luma weights.
+const LUMA_WEIGHTS: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+/// The classic sepia toning matrix, applied row by row to each RGB pixel.
+const SEPIA_MATRIX: [[f32; 3]; 3] = [
+    [0.393, 0.769, 0.189],
+    [0.349, 0.686, 0.168],
+    [0.272, 0.534, 0.131],
+];
+
+/// Apply a sepia tone, mixed with the original colours by `strength` (0.0 to 1.0).
+pub fn sepia(image: DynamicImage, strength: f32) -> DynamicImage {
+    map_rgb(image, |rgb| {
+        let toned: [f32; 3] = std::array::from_fn(|row| {
+            (0..3)
+                .map(|c| SEPIA_MATRIX[row][c] * rgb[c])
+                .sum::<f32>()
+                .min(1.0)
+        });
+        mix(rgb, toned, strength)
+    })
+}
+
+/// Map the luminance of each pixel onto a gradient between a shadow and a highlight colour.
+pub fn duotone(image: DynamicImage, shadow: Rgba<u8>, highlight: Rgba<u8>) -> DynamicImage {
+    let (shadow, highlight) = (normalise(shadow), normalise(highlight));
+    map_rgb(image, |rgb| mix(shadow, highlight, luma(rgb)))
+}
+
+/// Colourise an image with a single colour, mixed with the original colours by `strength` (0.0 to 1.0).
+pub fn tint(image: DynamicImage, color: Rgba<u8>, strength: f32) -> DynamicImage {
+    let color = normalise(color);
+    map_rgb(image, |rgb| {
+        let luma = luma(rgb);
+        mix(rgb, color.map(|c| c * luma), strength)
+    })
+}
+
+/// Run a function over the RGB channels of every pixel, preserving alpha and bit depth.
+/// Grayscale images are promoted to their colour equivalent.
+fn map_rgb(image: DynamicImage, f: impl Fn([f32; 3]) -> [f32; 3]) -> DynamicImage {
+    let color = color_equivalent(image.color());
+    let mut buffer = image.into_rgba32f();
+    for pixel in buffer.pixels_mut() {
+        let [r, g, b] = f([pixel[0], pixel[1], pixel[2]]);
+        pixel[0] = r;
+        pixel[1] = g;
+        pixel[2] = b;
+    }
+    into_color_type(DynamicImage::ImageRgba32F(buffer), color)
+}
+
+fn luma(rgb: [f32; 3]) -> f32 {
+    (0..3).map(|c| rgb[c] * LUMA_WEIGHTS[c]).sum()
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    std::array::from_fn(|c| a[c] + (b[c] - a[c]) * t)
+}
+
+fn normalise(color: Rgba<u8>) -> [f32; 3] {
+    [color[0], color[1], color[2]].map(|c| c as f32 / u8::MAX as f32)
+}
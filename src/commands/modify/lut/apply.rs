@@ -0,0 +1,97 @@
+use super::table::{Lut, LutInterpolation};
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::{color_equivalent, into_color_type};
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use std::path::PathBuf;
+
+/// Apply a .cube LUT or Hald CLUT image to an image.
+#[derive(Debug, Clone, Parser)]
+pub struct LutApplyCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// A path on disk to the look-up table to apply.
+    /// Files ending in .cube are read as 1D or 3D LUTs and all
+    /// other files are decoded as Hald CLUT images.
+    #[arg(long = "lut")]
+    pub lut_path: PathBuf,
+
+    /// The interpolation used when sampling between 3D LUT entries.
+    #[arg(long = "interpolation", default_value = "tetrahedral")]
+    pub interpolation: LutInterpolation,
+
+    /// How strongly the graded colours are mixed with the original colours (0.0 to 1.0).
+    #[arg(long = "intensity", default_value_t = 1.0)]
+    pub intensity: f32,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+impl ExecutableCommand for LutApplyCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() || !self.lut_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() || !self.lut_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+        if !(0.0..=1.0).contains(&self.intensity) {
+            bail!("LUT intensity must be between 0.0 and 1.0");
+        }
+
+        let is_cube = self
+            .lut_path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("cube"));
+        let lut = if is_cube {
+            Lut::from_cube_file(&self.lut_path)?
+        } else {
+            let hald = ImageReader::open(&self.lut_path)
+                .context(ERROR_IMGREAD_CTX)?
+                .decode()
+                .context(ERROR_IMGDECODE_CTX)?;
+            Lut::from_hald_image(&hald.into_rgb32f())?
+        };
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        let color = color_equivalent(image.color());
+
+        let mut buffer = image.into_rgba32f();
+        for pixel in buffer.pixels_mut() {
+            let original = [pixel[0], pixel[1], pixel[2]];
+            let graded = lut.sample(original, self.interpolation);
+            for c in 0..3 {
+                pixel[c] = original[c] + (graded[c] - original[c]) * self.intensity;
+            }
+        }
+
+        into_color_type(DynamicImage::ImageRgba32F(buffer), color)
+            .save_with_format(&self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
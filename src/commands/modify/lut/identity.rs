@@ -0,0 +1,48 @@
+use crate::commands::ExecutableCommand;
+use crate::commands::messages::{ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX, OUTPUT_ALREADY_EXISTS};
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use image::{ImageFormat, Rgb, RgbImage};
+use std::path::PathBuf;
+
+/// Generate an identity Hald CLUT image that can be edited and applied as a LUT.
+#[derive(Debug, Clone, Parser)]
+pub struct LutIdentityCommand {
+    /// A path on disk to where the output image should be placed.
+    /// A lossless format such as PNG should be used.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The Hald level of the CLUT (2 to 16). The image will be level³ pixels wide
+    /// and hold a level²-sized lattice (e.g. 8 produces a 512x512 image).
+    #[arg(long = "level", default_value_t = 8, value_parser = clap::value_parser!(u32).range(2..=16))]
+    pub level: u32,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+impl ExecutableCommand for LutIdentityCommand {
+    fn run(self) -> Result<()> {
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let side = self.level.pow(3);
+        let size = self.level * self.level;
+        let scale = u8::MAX as f32 / (size - 1) as f32;
+
+        RgbImage::from_fn(side, side, |x, y| {
+            let index = y * side + x;
+            let [r, g, b] = [index % size, (index / size) % size, index / (size * size)];
+            Rgb([r, g, b].map(|v| (v as f32 * scale).round() as u8))
+        })
+        .save_with_format(&self.output_path, output_format)
+        .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+mod apply;
+mod identity;
+mod table;
+
+use self::apply::LutApplyCommand;
+use self::identity::LutIdentityCommand;
+
+use crate::commands::ExecutableCommand;
+use anyhow::Result;
+use clap::Parser;
+
+/// Colour grade an image using .cube files or Hald CLUT images.
+#[derive(Debug, Parser)]
+pub struct LutCommand {
+    #[clap(subcommand)]
+    subcommand: LutSubcommand,
+}
+
+#[derive(Debug, Parser)]
+pub enum LutSubcommand {
+    Apply(LutApplyCommand),
+    Identity(LutIdentityCommand),
+}
+
+impl ExecutableCommand for LutCommand {
+    fn run(self) -> Result<()> {
+        match self.subcommand {
+            LutSubcommand::Apply(cmd) => cmd.run(),
+            LutSubcommand::Identity(cmd) => cmd.run(),
+        }
+    }
+}
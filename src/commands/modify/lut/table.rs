@@ -0,0 +1,334 @@
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use image::Rgb32FImage;
+use std::fs;
+use std::path::Path;
+
+/// The way values are sampled between the entries of a 3D look-up table.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum LutInterpolation {
+    /// Blend the 8 surrounding entries of the lattice cell.
+    Trilinear,
+
+    /// Blend the 4 entries of the tetrahedron containing the value (more accurate for neutrals).
+    Tetrahedral,
+}
+
+/// A colour look-up table with all values normalised to the domain of its entries.
+#[derive(Debug, Clone)]
+pub enum Lut {
+    /// A per-channel curve table.
+    OneDimensional {
+        size: usize,
+        domain_min: [f32; 3],
+        domain_max: [f32; 3],
+        entries: Vec<[f32; 3]>,
+    },
+
+    /// A lattice with red changing fastest, then green, then blue.
+    ThreeDimensional {
+        size: usize,
+        domain_min: [f32; 3],
+        domain_max: [f32; 3],
+        entries: Vec<[f32; 3]>,
+    },
+}
+
+impl Lut {
+    /// Parse an Adobe/Resolve `.cube` file containing either a 1D or 3D table.
+    pub fn from_cube_file(path: &Path) -> Result<Self> {
+        Self::parse_cube(&fs::read_to_string(path).context("failed to read .cube file")?)
+    }
+
+    fn parse_cube(contents: &str) -> Result<Self> {
+        let mut size_1d = None;
+        let mut size_3d = None;
+        let mut domain_min = [0.0; 3];
+        let mut domain_max = [1.0; 3];
+        let mut entries = Vec::new();
+
+        for (line_number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let keyword = parts.next().unwrap_or_default();
+            let parse_floats = |parts: std::str::SplitWhitespace| -> Result<Vec<f32>> {
+                parts
+                    .map(|v| v.parse::<f32>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .with_context(|| format!("invalid number on line {}", line_number + 1))
+            };
+
+            match keyword {
+                "LUT_1D_SIZE" => size_1d = Some(parse_size(parts.next(), line_number)?),
+                "LUT_3D_SIZE" => size_3d = Some(parse_size(parts.next(), line_number)?),
+                "DOMAIN_MIN" => domain_min = parse_triplet(parse_floats(parts)?, line_number)?,
+                "DOMAIN_MAX" => domain_max = parse_triplet(parse_floats(parts)?, line_number)?,
+                "LUT_1D_INPUT_RANGE" | "LUT_3D_INPUT_RANGE" => {
+                    let range = parse_floats(parts)?;
+                    if range.len() != 2 {
+                        bail!("expected a minimum and maximum on line {}", line_number + 1);
+                    }
+                    domain_min = [range[0]; 3];
+                    domain_max = [range[1]; 3];
+                }
+                // Skip TITLE and any other keywords, such as LUT_IN_VIDEO_RANGE, that
+                // don't change how the table is read.
+                _ if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {}
+                _ => {
+                    let values = parse_floats(line.split_whitespace())?;
+                    entries.push(parse_triplet(values, line_number)?);
+                }
+            }
+        }
+
+        if (0..3).any(|c| domain_max[c] <= domain_min[c]) {
+            bail!("the domain maximum of the LUT must be greater than its minimum");
+        }
+
+        match (size_1d, size_3d) {
+            (Some(size), None) => {
+                if entries.len() != size {
+                    bail!(
+                        "expected {} entries in 1D LUT but found {}",
+                        size,
+                        entries.len()
+                    );
+                }
+                Ok(Self::OneDimensional {
+                    size,
+                    domain_min,
+                    domain_max,
+                    entries,
+                })
+            }
+            (None, Some(size)) => {
+                if entries.len() != size.pow(3) {
+                    bail!(
+                        "expected {} entries in 3D LUT but found {}",
+                        size.pow(3),
+                        entries.len()
+                    );
+                }
+                Ok(Self::ThreeDimensional {
+                    size,
+                    domain_min,
+                    domain_max,
+                    entries,
+                })
+            }
+            (Some(_), Some(_)) => bail!("a .cube file cannot contain both a 1D and a 3D LUT"),
+            (None, None) => bail!("the .cube file does not specify a LUT_1D_SIZE or LUT_3D_SIZE"),
+        }
+    }
+
+    /// Build a 3D table from a Hald CLUT image, whose pixels list the lattice row by row.
+    pub fn from_hald_image(image: &Rgb32FImage) -> Result<Self> {
+        let (width, height) = image.dimensions();
+        let level = (width as f64).cbrt().round() as u32;
+        if width != height || level < 2 || level.pow(3) != width {
+            bail!(
+                "a Hald CLUT image must be square with a side length that is a cube (e.g. 512x512)"
+            );
+        }
+
+        let size = (level * level) as usize;
+        let entries = image.pixels().map(|p| p.0).collect();
+
+        Ok(Self::ThreeDimensional {
+            size,
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3],
+            entries,
+        })
+    }
+
+    /// Look up the graded value of an RGB colour.
+    pub fn sample(&self, rgb: [f32; 3], interpolation: LutInterpolation) -> [f32; 3] {
+        match self {
+            Self::OneDimensional {
+                size,
+                domain_min,
+                domain_max,
+                entries,
+            } => {
+                let mut out = [0.0; 3];
+                for c in 0..3 {
+                    let position =
+                        normalise(rgb[c], domain_min[c], domain_max[c]) * (*size - 1) as f32;
+                    let lower = position.floor() as usize;
+                    let upper = (lower + 1).min(size - 1);
+                    let fraction = position - lower as f32;
+                    out[c] = lerp(entries[lower][c], entries[upper][c], fraction);
+                }
+                out
+            }
+            Self::ThreeDimensional {
+                size,
+                domain_min,
+                domain_max,
+                entries,
+            } => {
+                let scale = (*size - 1) as f32;
+                let position: [f32; 3] = std::array::from_fn(|c| {
+                    normalise(rgb[c], domain_min[c], domain_max[c]) * scale
+                });
+                let lower = position.map(|p| p.floor() as usize);
+                let upper = lower.map(|l| (l + 1).min(size - 1));
+                let [fr, fg, fb] = std::array::from_fn(|c| position[c] - lower[c] as f32);
+                let at = |r: usize, g: usize, b: usize| entries[r + g * size + b * size * size];
+                let (r0, g0, b0) = (lower[0], lower[1], lower[2]);
+                let (r1, g1, b1) = (upper[0], upper[1], upper[2]);
+
+                match interpolation {
+                    LutInterpolation::Trilinear => {
+                        let c00 = mix(at(r0, g0, b0), at(r1, g0, b0), fr);
+                        let c10 = mix(at(r0, g1, b0), at(r1, g1, b0), fr);
+                        let c01 = mix(at(r0, g0, b1), at(r1, g0, b1), fr);
+                        let c11 = mix(at(r0, g1, b1), at(r1, g1, b1), fr);
+                        mix(mix(c00, c10, fg), mix(c01, c11, fg), fb)
+                    }
+                    LutInterpolation::Tetrahedral => {
+                        let c000 = at(r0, g0, b0);
+                        let c111 = at(r1, g1, b1);
+                        // Pick the tetrahedron by ordering the fractional offsets, then walk
+                        // from the lower corner to the upper corner along that path.
+                        let (w, a, b) = if fr > fg {
+                            if fg > fb {
+                                ([fr, fg, fb], at(r1, g0, b0), at(r1, g1, b0))
+                            } else if fr > fb {
+                                ([fr, fb, fg], at(r1, g0, b0), at(r1, g0, b1))
+                            } else {
+                                ([fb, fr, fg], at(r0, g0, b1), at(r1, g0, b1))
+                            }
+                        } else if fb > fg {
+                            ([fb, fg, fr], at(r0, g0, b1), at(r0, g1, b1))
+                        } else if fb > fr {
+                            ([fg, fb, fr], at(r0, g1, b0), at(r0, g1, b1))
+                        } else {
+                            ([fg, fr, fb], at(r0, g1, b0), at(r1, g1, b0))
+                        };
+                        std::array::from_fn(|c| {
+                            (1.0 - w[0]) * c000[c]
+                                + (w[0] - w[1]) * a[c]
+                                + (w[1] - w[2]) * b[c]
+                                + w[2] * c111[c]
+                        })
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn parse_size(value: Option<&str>, line_number: usize) -> Result<usize> {
+    let size = value
+        .and_then(|v| v.parse::<usize>().ok())
+        .with_context(|| format!("invalid LUT size on line {}", line_number + 1))?;
+    if size < 2 {
+        bail!("LUT size on line {} must be at least 2", line_number + 1);
+    }
+    Ok(size)
+}
+
+fn parse_triplet(values: Vec<f32>, line_number: usize) -> Result<[f32; 3]> {
+    values
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("expected 3 values on line {}", line_number + 1))
+}
+
+fn normalise(value: f32, min: f32, max: f32) -> f32 {
+    ((value - min) / (max - min)).clamp(0.0, 1.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    std::array::from_fn(|c| lerp(a[c], b[c], t))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDENTITY_3D: &str = "0 0 0\n1 0 0\n0 1 0\n1 1 0\n0 0 1\n1 0 1\n0 1 1\n1 1 1\n";
+
+    #[test]
+    fn parses_3d_cube_skipping_keywords() {
+        let contents = format!(
+            "# Comment\nTITLE \"Identity\"\nLUT_IN_VIDEO_RANGE\nLUT_3D_SIZE 2\n\n{IDENTITY_3D}"
+        );
+        let Lut::ThreeDimensional { size, entries, .. } = Lut::parse_cube(&contents).unwrap()
+        else {
+            panic!("expected a 3D LUT");
+        };
+        assert_eq!(size, 2);
+        assert_eq!(entries.len(), 8);
+        assert_eq!(entries[1], [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn parses_1d_cube_with_domain() {
+        let contents =
+            "LUT_1D_SIZE 3\nDOMAIN_MIN 0 0 0\nDOMAIN_MAX 2 2 2\n0 0 0\n0.5 0.5 0.5\n1 1 1\n";
+        let lut = Lut::parse_cube(contents).unwrap();
+        let Lut::OneDimensional {
+            size,
+            domain_max,
+            entries,
+            ..
+        } = &lut
+        else {
+            panic!("expected a 1D LUT");
+        };
+        assert_eq!(*size, 3);
+        assert_eq!(*domain_max, [2.0; 3]);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(lut.sample([1.0; 3], LutInterpolation::Trilinear), [0.5; 3]);
+    }
+
+    #[test]
+    fn parses_input_range() {
+        let contents = format!("LUT_3D_SIZE 2\nLUT_3D_INPUT_RANGE -1 1\n{IDENTITY_3D}");
+        let Lut::ThreeDimensional {
+            domain_min,
+            domain_max,
+            ..
+        } = Lut::parse_cube(&contents).unwrap()
+        else {
+            panic!("expected a 3D LUT");
+        };
+        assert_eq!(domain_min, [-1.0; 3]);
+        assert_eq!(domain_max, [1.0; 3]);
+    }
+
+    #[test]
+    fn rejects_wrong_number_of_entries() {
+        assert!(Lut::parse_cube("LUT_3D_SIZE 3\n0 0 0\n").is_err());
+        assert!(Lut::parse_cube("LUT_1D_SIZE 2\n0 0 0\n").is_err());
+    }
+
+    #[test]
+    fn rejects_missing_or_conflicting_sizes() {
+        assert!(Lut::parse_cube(IDENTITY_3D).is_err());
+        let contents = format!("LUT_1D_SIZE 8\nLUT_3D_SIZE 2\n{IDENTITY_3D}");
+        assert!(Lut::parse_cube(&contents).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_domain() {
+        let contents = format!("LUT_3D_SIZE 2\nDOMAIN_MIN 1 1 1\nDOMAIN_MAX 0 0 0\n{IDENTITY_3D}");
+        assert!(Lut::parse_cube(&contents).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_entries() {
+        assert!(Lut::parse_cube("LUT_1D_SIZE 2\n0 0\n1 1 1\n").is_err());
+        assert!(Lut::parse_cube("LUT_1D_SIZE 2\n0 0 x\n1 1 1\n").is_err());
+    }
+}
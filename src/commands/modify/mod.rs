@@ -1,26 +1,74 @@
+mod alpha;
+mod blend;
 mod blur;
 mod brighten;
+mod channels;
+mod chroma_key;
+mod composite;
 mod contrast;
+mod convolve;
 mod crop;
+mod denoise;
+mod draw;
+mod duotone;
+mod edges;
+mod effects;
 mod flip;
 mod format;
 mod grayscale;
 mod hue;
 mod invert;
+mod lut;
+mod morphology;
+mod overlay;
+mod pixel_scale;
+mod pixelate;
+mod posterize;
+mod preset;
+pub(super) mod quantize;
+mod redact;
 mod resize;
 mod rotate;
+mod sepia;
+mod text;
+mod threshold;
+mod tint;
+mod white_balance;
 
+use self::alpha::AlphaCommand;
+use self::blend::BlendCommand;
 use self::blur::BlurCommand;
 use self::brighten::BrightenCommand;
+use self::channels::ChannelsCommand;
+use self::chroma_key::ChromaKeyCommand;
 use self::contrast::ContrastCommand;
+use self::convolve::ConvolveCommand;
 use self::crop::CropCommand;
+use self::denoise::DenoiseCommand;
+use self::draw::DrawCommand;
+use self::duotone::DuotoneCommand;
+use self::edges::EdgesCommand;
 use self::flip::FlipCommand;
 use self::format::FormatCommand;
 use self::grayscale::GrayscaleCommand;
 use self::hue::HueCommand;
 use self::invert::InvertCommand;
+use self::lut::LutCommand;
+use self::morphology::MorphologyCommand;
+use self::overlay::OverlayCommand;
+use self::pixel_scale::PixelScaleCommand;
+use self::pixelate::PixelateCommand;
+use self::posterize::PosterizeCommand;
+use self::preset::PresetCommand;
+use self::quantize::QuantizeCommand;
+use self::redact::RedactCommand;
 use self::resize::ResizeCommand;
 use self::rotate::RotateCommand;
+use self::sepia::SepiaCommand;
+use self::text::TextCommand;
+use self::threshold::ThresholdCommand;
+use self::tint::TintCommand;
+use self::white_balance::WhiteBalanceCommand;
 
 use anyhow::Result;
 use clap::Parser;
@@ -38,17 +86,40 @@ pub struct ModifyCommandBase {
 
 #[derive(Debug, Parser)]
 pub enum ModifySubcommand {
+    Alpha(AlphaCommand),
+    Blend(BlendCommand),
     Blur(BlurCommand),
     Brighten(BrightenCommand),
+    Channels(ChannelsCommand),
+    ChromaKey(ChromaKeyCommand),
     Contrast(ContrastCommand),
+    Convolve(ConvolveCommand),
     Crop(CropCommand),
+    Denoise(DenoiseCommand),
+    Draw(DrawCommand),
+    Duotone(DuotoneCommand),
+    Edges(EdgesCommand),
     Flip(FlipCommand),
     Grayscale(GrayscaleCommand),
     Invert(InvertCommand),
+    Lut(LutCommand),
+    Morphology(MorphologyCommand),
+    Overlay(OverlayCommand),
+    Pixelate(PixelateCommand),
+    PixelScale(PixelScaleCommand),
+    Posterize(PosterizeCommand),
+    Preset(PresetCommand),
+    Quantize(QuantizeCommand),
+    Redact(RedactCommand),
     Hue(HueCommand),
     Format(FormatCommand),
     Resize(ResizeCommand),
     Rotate(RotateCommand),
+    Sepia(SepiaCommand),
+    Text(TextCommand),
+    Threshold(ThresholdCommand),
+    Tint(TintCommand),
+    WhiteBalance(WhiteBalanceCommand),
 }
 
 const PROGRESSBAR_TEMPLATE_RUNNING: &str = "🌸 [{elapsed}] {spinner} {msg}";
@@ -63,17 +134,40 @@ impl ExecutableCommand for ModifyCommandBase {
         progress_bar.enable_steady_tick(Duration::from_millis(PROGRESSBAR_TICK_RATE_MS));
 
         if let Err(err) = match self.subcommand {
+            ModifySubcommand::Alpha(cmd) => cmd.run(),
+            ModifySubcommand::Blend(cmd) => cmd.run(),
             ModifySubcommand::Blur(cmd) => cmd.run(),
             ModifySubcommand::Brighten(cmd) => cmd.run(),
+            ModifySubcommand::Channels(cmd) => cmd.run(),
+            ModifySubcommand::ChromaKey(cmd) => cmd.run(),
             ModifySubcommand::Contrast(cmd) => cmd.run(),
+            ModifySubcommand::Convolve(cmd) => cmd.run(),
             ModifySubcommand::Crop(cmd) => cmd.run(),
+            ModifySubcommand::Denoise(cmd) => cmd.run(),
+            ModifySubcommand::Draw(cmd) => cmd.run(),
+            ModifySubcommand::Duotone(cmd) => cmd.run(),
+            ModifySubcommand::Edges(cmd) => cmd.run(),
             ModifySubcommand::Flip(cmd) => cmd.run(),
             ModifySubcommand::Grayscale(cmd) => cmd.run(),
             ModifySubcommand::Invert(cmd) => cmd.run(),
+            ModifySubcommand::Lut(cmd) => cmd.run(),
+            ModifySubcommand::Morphology(cmd) => cmd.run(),
+            ModifySubcommand::Overlay(cmd) => cmd.run(),
+            ModifySubcommand::Pixelate(cmd) => cmd.run(),
+            ModifySubcommand::PixelScale(cmd) => cmd.run(),
+            ModifySubcommand::Posterize(cmd) => cmd.run(),
+            ModifySubcommand::Preset(cmd) => cmd.run(),
+            ModifySubcommand::Quantize(cmd) => cmd.run(),
+            ModifySubcommand::Redact(cmd) => cmd.run(),
             ModifySubcommand::Hue(cmd) => cmd.run(),
             ModifySubcommand::Format(cmd) => cmd.run(),
             ModifySubcommand::Resize(cmd) => cmd.run(),
             ModifySubcommand::Rotate(cmd) => cmd.run(),
+            ModifySubcommand::Sepia(cmd) => cmd.run(),
+            ModifySubcommand::Text(cmd) => cmd.run(),
+            ModifySubcommand::Threshold(cmd) => cmd.run(),
+            ModifySubcommand::Tint(cmd) => cmd.run(),
+            ModifySubcommand::WhiteBalance(cmd) => cmd.run(),
         } {
             progress_bar.finish_and_clear();
             return Err(err);
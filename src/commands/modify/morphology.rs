@@ -0,0 +1,277 @@
+use super::convolve::Kernel;
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::into_color_type;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::{DynamicImage, ImageFormat, ImageReader, Rgba32FImage};
+use rayon::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+
+/// Apply a morphological operation to an image.
+///
+/// Every channel (including alpha) is processed independently, which makes this
+/// most useful for cleaning up grayscale and binary masks. The gradient, top-hat and
+/// black-hat operations only take the difference of the colour channels.
+#[derive(Debug, Clone, Parser)]
+pub struct MorphologyCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The morphological operation to apply.
+    #[arg(long = "operation")]
+    pub operation: MorphologyOperation,
+
+    /// The shape of the structuring element.
+    #[arg(long = "element", default_value = "square")]
+    pub element: ElementShape,
+
+    /// The radius of the structuring element (1 to 64).
+    #[arg(
+        long = "radius",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..=64)
+    )]
+    pub radius: u32,
+
+    /// A custom structuring element with rows separated by ';' and values by ','
+    /// (e.g. '0,1,0;1,1,1;0,1,0'). Non-zero values are part of the element.
+    #[arg(long = "custom", conflicts_with_all = ["element", "radius", "custom_path"])]
+    pub custom: Option<String>,
+
+    /// A path on disk to a file containing a custom structuring element, with one row
+    /// per line and values separated by commas or whitespace.
+    #[arg(long = "custom-file", conflicts_with_all = ["element", "radius"])]
+    pub custom_path: Option<PathBuf>,
+
+    /// How many times the erosion and dilation making up the operation are repeated (1 to 100).
+    #[arg(
+        long = "iterations",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..=100)
+    )]
+    pub iterations: u32,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum MorphologyOperation {
+    /// Shrink bright areas, removing small bright specks.
+    Erode,
+
+    /// Grow bright areas, filling small dark holes.
+    Dilate,
+
+    /// Erode then dilate, removing bright details smaller than the element.
+    Open,
+
+    /// Dilate then erode, removing dark details smaller than the element.
+    Close,
+
+    /// The difference between the dilation and erosion, outlining shapes.
+    Gradient,
+
+    /// The difference between the image and its opening, keeping only small bright details.
+    TopHat,
+
+    /// The difference between the image's closing and itself, keeping only small dark details.
+    BlackHat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ElementShape {
+    /// A square of side 2 * radius + 1.
+    Square,
+
+    /// A disk of the given radius.
+    Disk,
+
+    /// A plus shaped cross with arms of the given radius.
+    Cross,
+}
+
+/// A structuring element as a sequence of neighbourhoods applied one after another,
+/// allowing separable shapes like squares to be processed as two lines.
+struct Element(Vec<Vec<(isize, isize)>>);
+
+impl Element {
+    fn from_shape(shape: ElementShape, radius: u32) -> Self {
+        let r = radius as isize;
+        let line = (-r..=r).collect::<Vec<_>>();
+        match shape {
+            ElementShape::Square => Self(vec![
+                line.iter().map(|x| (*x, 0)).collect(),
+                line.iter().map(|y| (0, *y)).collect(),
+            ]),
+            ElementShape::Disk => Self(vec![
+                line.iter()
+                    .flat_map(|y| line.iter().map(move |x| (*x, *y)))
+                    .filter(|(x, y)| x * x + y * y <= r * r + r / 2)
+                    .collect(),
+            ]),
+            ElementShape::Cross => Self(vec![
+                line.iter()
+                    .map(|x| (*x, 0))
+                    .chain(line.iter().filter(|y| **y != 0).map(|y| (0, *y)))
+                    .collect(),
+            ]),
+        }
+    }
+
+    fn from_kernel(kernel: &Kernel) -> Result<Self> {
+        let (anchor_x, anchor_y) = ((kernel.width / 2) as isize, (kernel.height / 2) as isize);
+        let offsets: Vec<_> = kernel
+            .values
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| **v != 0.0)
+            .map(|(i, _)| {
+                (
+                    (i % kernel.width) as isize - anchor_x,
+                    (i / kernel.width) as isize - anchor_y,
+                )
+            })
+            .collect();
+        if offsets.is_empty() {
+            bail!("the structuring element must contain at least one non-zero value");
+        }
+        Ok(Self(vec![offsets]))
+    }
+}
+
+impl ExecutableCommand for MorphologyCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+
+        let element = if let Some(custom) = &self.custom {
+            Element::from_kernel(&Kernel::parse(custom)?)?
+        } else if let Some(path) = &self.custom_path {
+            let contents =
+                fs::read_to_string(path).context("failed to read structuring element file")?;
+            Element::from_kernel(&Kernel::parse(&contents)?)?
+        } else {
+            Element::from_shape(self.element, self.radius)
+        };
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        let color = image.color();
+        let buffer = image.into_rgba32f();
+
+        let iterations = self.iterations;
+        let erode = |image: &Rgba32FImage| repeat(image, &element, iterations, Extremum::Min);
+        let dilate = |image: &Rgba32FImage| repeat(image, &element, iterations, Extremum::Max);
+        let output = match self.operation {
+            MorphologyOperation::Erode => erode(&buffer),
+            MorphologyOperation::Dilate => dilate(&buffer),
+            MorphologyOperation::Open => dilate(&erode(&buffer)),
+            MorphologyOperation::Close => erode(&dilate(&buffer)),
+            MorphologyOperation::Gradient => difference(&dilate(&buffer), &erode(&buffer)),
+            MorphologyOperation::TopHat => difference(&buffer, &dilate(&erode(&buffer))),
+            MorphologyOperation::BlackHat => difference(&erode(&dilate(&buffer)), &buffer),
+        };
+
+        into_color_type(DynamicImage::ImageRgba32F(output), color)
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Extremum {
+    Min,
+    Max,
+}
+
+fn repeat(
+    image: &Rgba32FImage,
+    element: &Element,
+    iterations: u32,
+    extremum: Extremum,
+) -> Rgba32FImage {
+    let mut output = image.clone();
+    for _ in 0..iterations {
+        for offsets in &element.0 {
+            output = filter(&output, offsets, extremum);
+        }
+    }
+    output
+}
+
+/// Replace every sample with the minimum or maximum of the samples under the
+/// element. Pixels outside of the image are ignored.
+fn filter(image: &Rgba32FImage, offsets: &[(isize, isize)], extremum: Extremum) -> Rgba32FImage {
+    let (width, height) = (image.width() as isize, image.height() as isize);
+    let samples = image.as_raw();
+    let mut output = image.clone();
+    output
+        .par_chunks_mut(width as usize * 4)
+        .enumerate()
+        .for_each(|(y, row)| {
+            let y = y as isize;
+            for x in 0..width {
+                let mut result = match extremum {
+                    Extremum::Min => [f32::INFINITY; 4],
+                    Extremum::Max => [f32::NEG_INFINITY; 4],
+                };
+                for (dx, dy) in offsets {
+                    let (sx, sy) = (x + dx, y + dy);
+                    if sx < 0 || sy < 0 || sx >= width || sy >= height {
+                        continue;
+                    }
+                    let i = (sy * width + sx) as usize * 4;
+                    for (value, sample) in result.iter_mut().zip(&samples[i..i + 4]) {
+                        *value = match extremum {
+                            Extremum::Min => value.min(*sample),
+                            Extremum::Max => value.max(*sample),
+                        };
+                    }
+                }
+                // Elements that miss the image entirely leave the pixel unchanged.
+                if result[0].is_finite() {
+                    row[x as usize * 4..][..4].copy_from_slice(&result);
+                }
+            }
+        });
+    output
+}
+
+/// Subtract the colour channels of `b` from `a`, keeping the more opaque of their alphas.
+fn difference(a: &Rgba32FImage, b: &Rgba32FImage) -> Rgba32FImage {
+    let mut output = a.clone();
+    for (pixel, other) in output.pixels_mut().zip(b.pixels()) {
+        for c in 0..3 {
+            pixel[c] = (pixel[c] - other[c]).max(0.0);
+        }
+        pixel[3] = pixel[3].max(other[3]);
+    }
+    output
+}
@@ -0,0 +1,131 @@
+use super::composite::composite;
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::{Gravity, into_color_type, layered_color, parse_offset};
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageFormat, ImageReader};
+use std::path::PathBuf;
+
+/// Place a second image, such as a logo or watermark, on top of an image.
+#[derive(Debug, Clone, Parser)]
+pub struct OverlayCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// A path on disk to the image that should be placed on top of the input.
+    #[arg(long = "overlay")]
+    pub overlay_path: PathBuf,
+
+    /// Where the overlay is anchored on the input image.
+    #[arg(long = "gravity", default_value = "center")]
+    pub gravity: Gravity,
+
+    /// How far in pixels to move the overlay inwards from the edges it is anchored to,
+    /// formatted as 'x,y'. Negative values move it outwards.
+    #[arg(long = "offset", default_value = "0,0", allow_hyphen_values = true, value_parser = parse_offset)]
+    pub offset: (i32, i32),
+
+    /// Resize the overlay to this fraction (0.0 to 1.0) of the input image's width,
+    /// keeping its aspect ratio. By default the overlay is placed at its original size.
+    #[arg(long = "scale")]
+    pub scale: Option<f32>,
+
+    /// The opacity (0.0 to 1.0) of the overlay.
+    #[arg(long = "opacity", default_value_t = 1.0)]
+    pub opacity: f32,
+
+    /// Repeat the overlay across the whole input image, starting from its anchored position.
+    #[arg(long = "tile", default_value_t = false)]
+    pub tile: bool,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+impl ExecutableCommand for OverlayCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() || !self.overlay_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() || !self.overlay_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+        if !(0.0..=1.0).contains(&self.opacity) {
+            bail!("opacity must be between 0.0 and 1.0");
+        }
+        if self.scale.is_some_and(|s| s <= 0.0 || s > 1.0) {
+            bail!("scale must be between 0.0 (exclusive) and 1.0");
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        let overlay = ImageReader::open(self.overlay_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+
+        let color = layered_color(image.color(), overlay.color());
+        let mut buffer = image.into_rgba32f();
+        let mut layer = overlay.into_rgba32f();
+
+        if let Some(scale) = self.scale {
+            let width = ((buffer.width() as f32 * scale).round() as u32).max(1);
+            let height = ((layer.height() as f32 * width as f32 / layer.width() as f32).round()
+                as u32)
+                .max(1);
+            layer = imageops::resize(&layer, width, height, FilterType::Lanczos3);
+        }
+
+        let (x, y) = self
+            .gravity
+            .position(buffer.dimensions(), layer.dimensions(), self.offset);
+        let normal = |_, source| source;
+        if self.tile {
+            let (step_x, step_y) = (layer.width() as i64, layer.height() as i64);
+            let mut tile_y = y.rem_euclid(step_y) - step_y;
+            while tile_y < buffer.height() as i64 {
+                let mut tile_x = x.rem_euclid(step_x) - step_x;
+                while tile_x < buffer.width() as i64 {
+                    composite(
+                        &mut buffer,
+                        &layer,
+                        (tile_x, tile_y),
+                        self.opacity,
+                        None,
+                        normal,
+                    );
+                    tile_x += step_x;
+                }
+                tile_y += step_y;
+            }
+        } else {
+            composite(&mut buffer, &layer, (x, y), self.opacity, None, normal);
+        }
+
+        into_color_type(DynamicImage::ImageRgba32F(buffer), color)
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
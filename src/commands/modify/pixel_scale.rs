@@ -0,0 +1,457 @@
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::into_color_type;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::{DynamicImage, ImageFormat, ImageReader, Rgba32FImage};
+use rayon::prelude::*;
+use std::path::PathBuf;
+
+/// Scale pixel art up with an algorithm designed to smooth its diagonals while
+/// keeping edges sharp.
+///
+/// Transparent pixels are treated as identical regardless of their hidden colour,
+/// and blending is done with premultiplied alpha so no colour fringes appear.
+#[derive(Debug, Clone, Parser)]
+pub struct PixelScaleCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The scaling algorithm to use, which also determines the scale factor.
+    #[arg(long = "algorithm")]
+    pub algorithm: PixelScaleAlgorithm,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum PixelScaleAlgorithm {
+    /// Scale by 2x, copying neighbouring colours into corners that sit on a diagonal edge.
+    Scale2x,
+
+    /// Scale by 3x using the Scale3x rules.
+    Scale3x,
+
+    /// Scale by 4x by applying Scale2x twice.
+    Scale4x,
+
+    /// Scale by 2x using Eric's Pixel Expansion, which Scale2x was derived from and produces the same result.
+    Epx,
+
+    /// Scale by 2x, blending corners based on which neighbours are perceptually different in YUV space
+    /// (similar to hqx, but without its lookup tables).
+    Yuv2x,
+
+    /// Scale by 3x using YUV blending.
+    Yuv3x,
+
+    /// Scale by 4x using YUV blending.
+    Yuv4x,
+
+    /// Scale by 2x, weighing edge directions over a 5x5 neighbourhood to smooth shallow and steep slopes.
+    Xbr2x,
+
+    /// Scale by 3x using xBR.
+    Xbr3x,
+
+    /// Scale by 4x using xBR.
+    Xbr4x,
+}
+
+impl ExecutableCommand for PixelScaleCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        let color = image.color();
+        let source = Source::new(&image.into_rgba32f());
+
+        let scaled = match self.algorithm {
+            PixelScaleAlgorithm::Scale2x | PixelScaleAlgorithm::Epx => {
+                source.scale(2, scale2x_block)
+            }
+            PixelScaleAlgorithm::Scale3x => source.scale(3, scale3x_block),
+            PixelScaleAlgorithm::Scale4x => source.scale(2, scale2x_block).scale(2, scale2x_block),
+            PixelScaleAlgorithm::Yuv2x => yuv_blend(&source, 2),
+            PixelScaleAlgorithm::Yuv3x => yuv_blend(&source, 3),
+            PixelScaleAlgorithm::Yuv4x => yuv_blend(&source, 4),
+            PixelScaleAlgorithm::Xbr2x => xbr(&source, 2),
+            PixelScaleAlgorithm::Xbr3x => xbr(&source, 3),
+            PixelScaleAlgorithm::Xbr4x => xbr(&source, 4),
+        };
+
+        into_color_type(DynamicImage::ImageRgba32F(scaled.into_image()), color)
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
+
+type Pixel = [f32; 4];
+
+/// An image of premultiplied pixels that can be sampled with its edges repeated.
+struct Source {
+    width: usize,
+    height: usize,
+    pixels: Vec<Pixel>,
+}
+
+impl Source {
+    fn new(buffer: &Rgba32FImage) -> Self {
+        let pixels = buffer
+            .pixels()
+            .map(|p| {
+                let a = p[3];
+                [p[0] * a, p[1] * a, p[2] * a, a]
+            })
+            .collect();
+        Self {
+            width: buffer.width() as usize,
+            height: buffer.height() as usize,
+            pixels,
+        }
+    }
+
+    fn get(&self, x: i64, y: i64) -> Pixel {
+        let x = x.clamp(0, self.width as i64 - 1) as usize;
+        let y = y.clamp(0, self.height as i64 - 1) as usize;
+        self.pixels[y * self.width + x]
+    }
+
+    /// Build an image `factor` times larger by having `block` fill the
+    /// `factor` x `factor` block of output pixels for every source pixel.
+    fn scale(&self, factor: usize, block: impl Fn(&Self, i64, i64, &mut [Pixel]) + Sync) -> Self {
+        let width = self.width * factor;
+        let mut pixels = vec![[0.0; 4]; width * self.height * factor];
+        pixels
+            .par_chunks_mut(width * factor)
+            .enumerate()
+            .for_each(|(y, rows)| {
+                let mut output = vec![[0.0; 4]; factor * factor];
+                for x in 0..self.width {
+                    block(self, x as i64, y as i64, &mut output);
+                    for (by, row) in output.chunks(factor).enumerate() {
+                        rows[by * width + x * factor..][..factor].copy_from_slice(row);
+                    }
+                }
+            });
+        Self {
+            width,
+            height: self.height * factor,
+            pixels,
+        }
+    }
+
+    fn into_image(self) -> Rgba32FImage {
+        let samples = self
+            .pixels
+            .into_iter()
+            .flat_map(|[r, g, b, a]| {
+                if a > 0.0 {
+                    [r / a, g / a, b / a, a]
+                } else {
+                    [0.0; 4]
+                }
+            })
+            .collect();
+        Rgba32FImage::from_raw(self.width as u32, self.height as u32, samples)
+            .expect("the buffer matches the image dimensions")
+    }
+}
+
+fn scale2x_block(source: &Source, x: i64, y: i64, output: &mut [Pixel]) {
+    let [b, d, e, f, h] =
+        [(0, -1), (-1, 0), (0, 0), (1, 0), (0, 1)].map(|(dx, dy)| source.get(x + dx, y + dy));
+    output.fill(e);
+    if b != h && d != f {
+        if d == b {
+            output[0] = d;
+        }
+        if b == f {
+            output[1] = f;
+        }
+        if d == h {
+            output[2] = d;
+        }
+        if h == f {
+            output[3] = f;
+        }
+    }
+}
+
+fn scale3x_block(source: &Source, x: i64, y: i64, output: &mut [Pixel]) {
+    let [a, b, c, d, e, f, g, h, i] =
+        std::array::from_fn(|n| source.get(x + n as i64 % 3 - 1, y + n as i64 / 3 - 1));
+    output.fill(e);
+    if b != h && d != f {
+        if d == b {
+            output[0] = d;
+        }
+        if (d == b && e != c) || (b == f && e != a) {
+            output[1] = b;
+        }
+        if b == f {
+            output[2] = f;
+        }
+        if (d == b && e != g) || (d == h && e != a) {
+            output[3] = d;
+        }
+        if (b == f && e != i) || (h == f && e != c) {
+            output[5] = f;
+        }
+        if d == h {
+            output[6] = d;
+        }
+        if (d == h && e != i) || (h == f && e != g) {
+            output[7] = h;
+        }
+        if h == f {
+            output[8] = f;
+        }
+    }
+}
+
+/// The shape of the edge cutting across the bottom right corner of a pixel,
+/// before being rotated to face the corner being filtered.
+#[derive(Clone, Copy)]
+enum Edge {
+    /// A 45 degree diagonal.
+    Diagonal,
+    /// A slope of one pixel down for every two across.
+    Shallow,
+    /// A slope of two pixels down for every one across.
+    Steep,
+    /// Both the shallow and steep slopes, for a corner jutting into a region.
+    ShallowAndSteep,
+    /// Only the output pixel closest to the corner.
+    Corner,
+}
+
+/// How much of each output pixel in a scaled block is covered by each kind of
+/// edge, for every corner of the block.
+struct Coverage {
+    factor: usize,
+    tables: [[Vec<f32>; 5]; 4],
+}
+
+impl Coverage {
+    const SAMPLES: usize = 16;
+
+    fn new(factor: usize) -> Self {
+        let tables = std::array::from_fn(|corner| {
+            [
+                Edge::Diagonal,
+                Edge::Shallow,
+                Edge::Steep,
+                Edge::ShallowAndSteep,
+                Edge::Corner,
+            ]
+            .map(|edge| Self::table(factor, corner, edge))
+        });
+        Self { factor, tables }
+    }
+
+    /// The fraction of each output pixel on the far side of the edge, found by supersampling.
+    fn table(factor: usize, corner: usize, edge: Edge) -> Vec<f32> {
+        let inside = |x: f32, y: f32| match edge {
+            Edge::Diagonal => x + y > 0.5,
+            Edge::Shallow => x + 2.0 * y > 0.5,
+            Edge::Steep => 2.0 * x + y > 0.5,
+            Edge::ShallowAndSteep => x + 2.0 * y > 0.5 || 2.0 * x + y > 0.5,
+            Edge::Corner => {
+                let limit = 0.5 - 1.0 / factor as f32;
+                x > limit && y > limit
+            }
+        };
+        let step = 1.0 / (factor * Self::SAMPLES) as f32;
+        let mut table = vec![0.0; factor * factor];
+        for (n, value) in table.iter_mut().enumerate() {
+            let (bx, by) = (n % factor, n / factor);
+            let mut count = 0;
+            for sy in 0..Self::SAMPLES {
+                for sx in 0..Self::SAMPLES {
+                    let x = ((bx * Self::SAMPLES + sx) as f32 + 0.5) * step - 0.5;
+                    let y = ((by * Self::SAMPLES + sy) as f32 + 0.5) * step - 0.5;
+                    // Undo the rotation that took the bottom right corner to this one.
+                    let (x, y) = (0..corner).fold((x, y), |(x, y), _| (y, -x));
+                    if inside(x, y) {
+                        count += 1;
+                    }
+                }
+            }
+            *value = count as f32 / (Self::SAMPLES * Self::SAMPLES) as f32;
+        }
+        table
+    }
+
+    /// Blend `color` into the output block over the area covered by `edge` in `corner`.
+    fn apply(&self, output: &mut [Pixel], corner: usize, edge: Edge, color: Pixel, amount: f32) {
+        let table = &self.tables[corner][edge as usize];
+        for (pixel, coverage) in output.iter_mut().zip(table) {
+            let t = coverage * amount;
+            if t > 0.0 {
+                for c in 0..4 {
+                    pixel[c] += (color[c] - pixel[c]) * t;
+                }
+            }
+        }
+    }
+
+    fn scale(
+        &self,
+        source: &Source,
+        filter: impl Fn(&Neighbours) -> Option<(Edge, Pixel, f32)> + Sync,
+    ) -> Source {
+        source.scale(self.factor, |source, x, y, output| {
+            output.fill(source.get(x, y));
+            for corner in 0..4 {
+                let neighbours = Neighbours {
+                    source,
+                    x,
+                    y,
+                    corner,
+                };
+                if let Some((edge, color, amount)) = filter(&neighbours) {
+                    self.apply(output, corner, edge, color, amount);
+                }
+            }
+        })
+    }
+}
+
+/// The pixels around a source pixel, rotated so that the corner being
+/// filtered is always towards positive x and y.
+struct Neighbours<'a> {
+    source: &'a Source,
+    x: i64,
+    y: i64,
+    corner: usize,
+}
+
+impl Neighbours<'_> {
+    fn at(&self, dx: i64, dy: i64) -> Pixel {
+        let (dx, dy) = (0..self.corner).fold((dx, dy), |(dx, dy), _| (-dy, dx));
+        self.source.get(self.x + dx, self.y + dy)
+    }
+}
+
+fn yuv([r, g, b, a]: Pixel) -> [f32; 4] {
+    [
+        0.299 * r + 0.587 * g + 0.114 * b,
+        -0.169 * r - 0.331 * g + 0.5 * b,
+        0.5 * r - 0.419 * g - 0.081 * b,
+        a,
+    ]
+}
+
+fn average(a: Pixel, b: Pixel) -> Pixel {
+    std::array::from_fn(|c| (a[c] + b[c]) / 2.0)
+}
+
+fn yuv_blend(source: &Source, factor: usize) -> Source {
+    // The thresholds used by hqx, in units of 0 to 255.
+    const THRESHOLDS: [f32; 4] = [48.0, 7.0, 6.0, 48.0];
+    let differ = |a: Pixel, b: Pixel| {
+        let (a, b) = (yuv(a), yuv(b));
+        (0..4).any(|c| (a[c] - b[c]).abs() * 255.0 > THRESHOLDS[c])
+    };
+
+    Coverage::new(factor).scale(source, |n| {
+        let (e, f, h, i) = (n.at(0, 0), n.at(1, 0), n.at(0, 1), n.at(1, 1));
+        let (differ_f, differ_h) = (differ(e, f), differ(e, h));
+
+        if differ_f && differ_h && !differ(f, h) {
+            let color = average(f, h);
+            if !differ(e, i) {
+                // A thin diagonal line passes through the corner, so only soften it.
+                return Some((Edge::Diagonal, color, 0.5));
+            }
+            let shallow = !differ(h, n.at(-1, 1)) && !differ(e, n.at(-1, 0));
+            let steep = !differ(f, n.at(1, -1)) && !differ(e, n.at(0, -1));
+            let edge = match (shallow, steep) {
+                (true, true) => Edge::ShallowAndSteep,
+                (true, false) => Edge::Shallow,
+                (false, true) => Edge::Steep,
+                (false, false) => Edge::Diagonal,
+            };
+            Some((edge, color, 1.0))
+        } else {
+            None
+        }
+    })
+}
+
+fn xbr(source: &Source, factor: usize) -> Source {
+    // The weighted YUV distance used by xBR, and the distance under which colours are equal.
+    const WEIGHTS: [f32; 4] = [32.0, 7.0, 6.0, 32.0];
+    const EQUAL: f32 = 155.0 / 255.0;
+    let df = |a: Pixel, b: Pixel| {
+        let (a, b) = (yuv(a), yuv(b));
+        (0..4)
+            .map(|c| (a[c] - b[c]).abs() * WEIGHTS[c])
+            .sum::<f32>()
+    };
+    let eq = |a: Pixel, b: Pixel| df(a, b) < EQUAL;
+
+    Coverage::new(factor).scale(source, |n| {
+        let e = n.at(0, 0);
+        let (b, c, d, f) = (n.at(0, -1), n.at(1, -1), n.at(-1, 0), n.at(1, 0));
+        let (g, h, i) = (n.at(-1, 1), n.at(0, 1), n.at(1, 1));
+        if e == f || e == h {
+            return None;
+        }
+        let (f4, i4, h5, i5) = (n.at(2, 0), n.at(2, 1), n.at(0, 2), n.at(1, 2));
+
+        let edge_weight = df(e, c) + df(e, g) + df(i, h5) + df(i, f4) + 4.0 * df(h, f);
+        let across_weight = df(h, d) + df(h, i5) + df(f, i4) + df(f, b) + 4.0 * df(e, i);
+        let color = if df(e, f) <= df(e, h) { f } else { h };
+
+        if edge_weight < across_weight
+            && ((!eq(f, b) && !eq(h, d))
+                || (eq(e, i) && !eq(f, i4) && !eq(h, i5))
+                || eq(e, g)
+                || eq(e, c))
+        {
+            let (shallow_weight, steep_weight) = (df(f, g), df(h, c));
+            let shallow = 2.0 * shallow_weight <= steep_weight && e != g && d != g;
+            let steep = shallow_weight >= 2.0 * steep_weight && e != c && b != c;
+            let edge = match (shallow, steep) {
+                (true, true) => Edge::ShallowAndSteep,
+                (true, false) => Edge::Shallow,
+                (false, true) => Edge::Steep,
+                (false, false) => Edge::Diagonal,
+            };
+            Some((edge, color, 1.0))
+        } else if edge_weight <= across_weight {
+            Some((Edge::Corner, color, 0.5))
+        } else {
+            None
+        }
+    })
+}
@@ -0,0 +1,219 @@
+use super::quantize::{DitherMethod, QuantizeAlgorithm, histogram, load_palette};
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::{color_equivalent, into_color_type};
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::{DynamicImage, ImageFormat, ImageReader, Rgba, Rgba32FImage};
+use std::path::PathBuf;
+
+/// Pixelate an image into a mosaic of blocks, or scale pixel art up without blurring.
+#[derive(Debug, Clone, Parser)]
+pub struct PixelateCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The size in pixels of each square block (1 to 1024).
+    #[arg(
+        long = "block-size",
+        value_parser = clap::value_parser!(u32).range(1..=1024)
+    )]
+    pub block_size: Option<u32>,
+
+    /// The number of blocks across and down the image, formatted as 'columns' or 'columnsxrows'.
+    /// When only columns are given, rows are chosen to keep the blocks roughly square.
+    #[arg(long = "grid", conflicts_with = "block_size", value_parser = parse_grid)]
+    pub grid: Option<(u32, Option<u32>)>,
+
+    /// How the colour of each block is chosen.
+    #[arg(long = "sampling", default_value = "average")]
+    pub sampling: Sampling,
+
+    /// Restrict the blocks to a palette of at most this many colours (2 to 256).
+    #[arg(long = "colors", value_parser = clap::value_parser!(u16).range(2..=256))]
+    pub colors: Option<u16>,
+
+    /// The algorithm used to generate the palette when restricting colours.
+    #[arg(long = "algorithm", default_value = "median-cut", requires = "colors")]
+    pub algorithm: QuantizeAlgorithm,
+
+    /// A path on disk to a fixed palette to restrict the blocks to.
+    /// GIMP palettes (.gpl), Adobe Color Tables (.act) and swatch images are supported.
+    #[arg(long = "palette", conflicts_with = "colors")]
+    pub palette_path: Option<PathBuf>,
+
+    /// Scale the result up by this whole number factor (2 to 64) using nearest neighbour
+    /// sampling, keeping pixel art crisp. Can be used on its own.
+    #[arg(
+        long = "upscale-nearest",
+        value_parser = clap::value_parser!(u32).range(2..=64)
+    )]
+    pub upscale: Option<u32>,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Sampling {
+    /// Use the average colour of each block.
+    Average,
+
+    /// Use the colour of the pixel at the centre of each block.
+    Nearest,
+}
+
+fn parse_grid(value: &str) -> Result<(u32, Option<u32>), String> {
+    let parse = |v: &str| match v.trim().parse::<u32>() {
+        Ok(0) => Err("the grid must have at least one column and row".to_string()),
+        other => other.map_err(|e| e.to_string()),
+    };
+    match value.split_once(['x', 'X']) {
+        Some((columns, rows)) => Ok((parse(columns)?, Some(parse(rows)?))),
+        None => Ok((parse(value)?, None)),
+    }
+}
+
+impl ExecutableCommand for PixelateCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+        let restricts_palette = self.colors.is_some() || self.palette_path.is_some();
+        if self.block_size.is_none()
+            && self.grid.is_none()
+            && self.upscale.is_none()
+            && !restricts_palette
+        {
+            bail!(
+                "one of --block-size, --grid, --upscale-nearest, --colors or --palette must be given"
+            );
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        // Palettes may add colour to grayscale images.
+        let color = if restricts_palette {
+            color_equivalent(image.color())
+        } else {
+            image.color()
+        };
+        let buffer = image.into_rgba32f();
+        let (width, height) = buffer.dimensions();
+
+        // Work out where each block starts and ends along both axes.
+        let (column_edges, row_edges) = match (self.block_size, self.grid) {
+            (Some(size), _) => (block_edges(width, size), block_edges(height, size)),
+            (_, Some((columns, rows))) => {
+                let columns = columns.min(width);
+                let rows = rows
+                    .unwrap_or_else(|| {
+                        (height as f32 * columns as f32 / width as f32).round() as u32
+                    })
+                    .clamp(1, height);
+                (grid_edges(width, columns), grid_edges(height, rows))
+            }
+            _ => (block_edges(width, 1), block_edges(height, 1)),
+        };
+
+        let mut blocks = Rgba32FImage::from_fn(
+            column_edges.len() as u32 - 1,
+            row_edges.len() as u32 - 1,
+            |bx, by| {
+                let (x0, x1) = (column_edges[bx as usize], column_edges[bx as usize + 1]);
+                let (y0, y1) = (row_edges[by as usize], row_edges[by as usize + 1]);
+                match self.sampling {
+                    Sampling::Nearest => *buffer.get_pixel((x0 + x1) / 2, (y0 + y1) / 2),
+                    Sampling::Average => {
+                        let mut sum = [0.0f64; 4];
+                        for y in y0..y1 {
+                            for x in x0..x1 {
+                                for (total, value) in sum.iter_mut().zip(buffer.get_pixel(x, y).0) {
+                                    *total += value as f64;
+                                }
+                            }
+                        }
+                        let count = ((x1 - x0) * (y1 - y0)) as f64;
+                        Rgba(sum.map(|v| (v / count) as f32))
+                    }
+                }
+            },
+        );
+
+        if restricts_palette {
+            let blocks8 = DynamicImage::ImageRgba32F(blocks.clone()).into_rgba8();
+            let palette = match (&self.palette_path, self.colors) {
+                (Some(path), _) => load_palette(path)?,
+                (None, colors) => self.algorithm.build_palette(
+                    &histogram(blocks8.pixels().map(|p| [p[0], p[1], p[2]])),
+                    colors.unwrap_or(256) as usize,
+                ),
+            };
+            let indices = DitherMethod::None.map_pixels(&blocks8, &palette, None);
+            for (pixel, index) in blocks.pixels_mut().zip(indices) {
+                let [r, g, b] = palette[index as usize].map(|c| c as f32 / 255.0);
+                pixel.0 = [r, g, b, pixel[3]];
+            }
+        }
+
+        let factor = self.upscale.unwrap_or(1);
+        let (Some(output_width), Some(output_height)) =
+            (width.checked_mul(factor), height.checked_mul(factor))
+        else {
+            bail!("the upscaled image would be too large");
+        };
+        // Map every output pixel back to the block it falls in.
+        let block_index =
+            |edges: &[u32], position: u32| edges.partition_point(|e| *e <= position) - 1;
+        let columns: Vec<usize> = (0..width).map(|x| block_index(&column_edges, x)).collect();
+        let rows: Vec<usize> = (0..height).map(|y| block_index(&row_edges, y)).collect();
+        let output = Rgba32FImage::from_fn(output_width, output_height, |x, y| {
+            *blocks.get_pixel(
+                columns[(x / factor) as usize] as u32,
+                rows[(y / factor) as usize] as u32,
+            )
+        });
+
+        into_color_type(DynamicImage::ImageRgba32F(output), color)
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
+
+/// The edges of blocks of a fixed size along an axis, with the last block cut short.
+fn block_edges(length: u32, size: u32) -> Vec<u32> {
+    (0..length)
+        .step_by(size as usize)
+        .chain(std::iter::once(length))
+        .collect()
+}
+
+/// The edges of a number of evenly sized blocks along an axis.
+fn grid_edges(length: u32, count: u32) -> Vec<u32> {
+    (0..=count)
+        .map(|i| (i as u64 * length as u64 / count as u64) as u32)
+        .collect()
+}
@@ -0,0 +1,203 @@
+use super::effects;
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::parse_color;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, anyhow, bail};
+use clap::Parser;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Apply a named "look" made up of several modifications to an image.
+///
+/// Built-in presets are: cool, faded, noir, vintage and warm. Additional presets can be
+/// defined in a TOML file, which by default is read from 'imgutils/presets.toml' inside
+/// the user's configuration directory. User presets take priority over built-in ones.
+///
+/// Example presets file:
+///
+/// [presets.polaroid]
+/// steps = [
+///   { op = "sepia", strength = 0.3 },
+///   { op = "contrast", amount = -15.0 },
+///   { op = "tint", color = "#ffe6b3", strength = 0.1 },
+/// ]
+#[derive(Debug, Clone, Parser)]
+#[command(verbatim_doc_comment)]
+pub struct PresetCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The name of the preset to apply.
+    #[arg(long = "name")]
+    pub name: String,
+
+    /// A path on disk to a TOML file containing user-defined presets.
+    #[arg(long = "presets-file")]
+    pub presets_path: Option<PathBuf>,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct PresetsFile {
+    #[serde(default)]
+    presets: BTreeMap<String, Preset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Preset {
+    steps: Vec<PresetStep>,
+}
+
+/// A single modification performed as part of a preset.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "kebab-case")]
+enum PresetStep {
+    Blur { strength: f32 },
+    Brighten { amount: i32 },
+    Contrast { amount: f32 },
+    Duotone { shadow: String, highlight: String },
+    Grayscale,
+    Hue { shift: i32 },
+    Invert,
+    Sepia { strength: f32 },
+    Tint { color: String, strength: f32 },
+}
+
+impl PresetStep {
+    fn apply(&self, mut image: DynamicImage) -> Result<DynamicImage> {
+        Ok(match self {
+            Self::Blur { strength } => image.blur(*strength),
+            Self::Brighten { amount } => image.brighten(*amount),
+            Self::Contrast { amount } => image.adjust_contrast(*amount),
+            Self::Duotone { shadow, highlight } => effects::duotone(
+                image,
+                parse_color(shadow).map_err(|e| anyhow!(e))?,
+                parse_color(highlight).map_err(|e| anyhow!(e))?,
+            ),
+            Self::Grayscale => image.grayscale(),
+            Self::Hue { shift } => image.huerotate(*shift),
+            Self::Invert => {
+                image.invert();
+                image
+            }
+            Self::Sepia { strength } => effects::sepia(image, strength.clamp(0.0, 1.0)),
+            Self::Tint { color, strength } => effects::tint(
+                image,
+                parse_color(color).map_err(|e| anyhow!(e))?,
+                strength.clamp(0.0, 1.0),
+            ),
+        })
+    }
+}
+
+fn builtin_presets() -> BTreeMap<String, Preset> {
+    let tint = |color: &str, strength: f32| PresetStep::Tint {
+        color: color.to_string(),
+        strength,
+    };
+    BTreeMap::from([
+        (
+            "cool".to_string(),
+            Preset {
+                steps: vec![tint("#7fb2ff", 0.15)],
+            },
+        ),
+        (
+            "faded".to_string(),
+            Preset {
+                steps: vec![
+                    PresetStep::Contrast { amount: -25.0 },
+                    PresetStep::Brighten { amount: 15 },
+                ],
+            },
+        ),
+        (
+            "noir".to_string(),
+            Preset {
+                steps: vec![PresetStep::Grayscale, PresetStep::Contrast { amount: 30.0 }],
+            },
+        ),
+        (
+            "vintage".to_string(),
+            Preset {
+                steps: vec![
+                    PresetStep::Sepia { strength: 0.6 },
+                    PresetStep::Contrast { amount: -10.0 },
+                    PresetStep::Brighten { amount: 10 },
+                ],
+            },
+        ),
+        (
+            "warm".to_string(),
+            Preset {
+                steps: vec![tint("#ffb46b", 0.15)],
+            },
+        ),
+    ])
+}
+
+impl ExecutableCommand for PresetCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+
+        let mut presets = builtin_presets();
+        let presets_path = self.presets_path.clone().or_else(|| {
+            dirs::config_dir()
+                .map(|dir| dir.join("imgutils").join("presets.toml"))
+                .filter(|path| path.is_file())
+        });
+        if let Some(path) = presets_path {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read presets file {}", path.display()))?;
+            let file: PresetsFile = toml::from_str(&contents)
+                .with_context(|| format!("failed to parse presets file {}", path.display()))?;
+            presets.extend(file.presets);
+        }
+        let Some(preset) = presets.get(&self.name) else {
+            bail!(
+                "unknown preset '{}', available presets are: {}",
+                self.name,
+                presets.keys().cloned().collect::<Vec<_>>().join(", ")
+            );
+        };
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let mut image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        for step in &preset.steps {
+            image = step.apply(image)?;
+        }
+        image
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
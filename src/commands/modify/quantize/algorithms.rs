@@ -0,0 +1,272 @@
+use clap::ValueEnum;
+use color_quant::NeuQuant;
+use std::collections::HashMap;
+
+/// A unique colour and the number of pixels that use it.
+#[derive(Debug, Clone, Copy)]
+pub struct ColorCount {
+    pub rgb: [u8; 3],
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum QuantizeAlgorithm {
+    /// Repeatedly split the colour space at the median of its widest channel.
+    MedianCut,
+
+    /// Merge the least significant branches of an octree of all colours.
+    Octree,
+
+    /// Refine a median cut palette by k-means clustering (slowest, usually most accurate).
+    KMeans,
+
+    /// Train a Kohonen neural network on the image's colours (NeuQuant).
+    Neuquant,
+}
+
+/// The maximum number of unique colours considered by k-means before colours are bucketed.
+const KMEANS_MAX_COLORS: usize = 1 << 16;
+const KMEANS_ITERATIONS: usize = 16;
+
+/// The NeuQuant sampling factor, where 1 is the slowest and highest quality and 30 the fastest.
+const NEUQUANT_SAMPLE_FACTOR: i32 = 10;
+
+impl QuantizeAlgorithm {
+    /// Build a palette of at most `max_colors` colours from a colour histogram.
+    pub fn build_palette(self, histogram: &[ColorCount], max_colors: usize) -> Vec<[u8; 3]> {
+        if histogram.len() <= max_colors {
+            return histogram.iter().map(|c| c.rgb).collect();
+        }
+        match self {
+            Self::MedianCut => median_cut(histogram, max_colors),
+            Self::Octree => octree(histogram, max_colors),
+            Self::KMeans => k_means(histogram, max_colors),
+            Self::Neuquant => neuquant(histogram, max_colors),
+        }
+    }
+}
+
+/// Count the unique colours of an iterator of RGB pixels.
+pub fn histogram(pixels: impl Iterator<Item = [u8; 3]>) -> Vec<ColorCount> {
+    let mut counts: HashMap<[u8; 3], u64> = HashMap::new();
+    for rgb in pixels {
+        *counts.entry(rgb).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .map(|(rgb, count)| ColorCount { rgb, count })
+        .collect()
+}
+
+fn weighted_mean(colors: &[ColorCount]) -> [u8; 3] {
+    let total: u64 = colors.iter().map(|c| c.count).sum();
+    std::array::from_fn(|channel| {
+        let sum: u64 = colors.iter().map(|c| c.rgb[channel] as u64 * c.count).sum();
+        ((sum + total / 2) / total.max(1)) as u8
+    })
+}
+
+fn median_cut(histogram: &[ColorCount], max_colors: usize) -> Vec<[u8; 3]> {
+    let channel_range = |colors: &[ColorCount], channel: usize| {
+        let (min, max) = colors.iter().fold((u8::MAX, 0), |(min, max), c| {
+            (min.min(c.rgb[channel]), max.max(c.rgb[channel]))
+        });
+        max.saturating_sub(min)
+    };
+
+    let mut boxes = vec![histogram.to_vec()];
+    while boxes.len() < max_colors {
+        // Split the box covering the widest range of any channel.
+        let Some((index, channel, _)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .flat_map(|(i, colors)| (0..3).map(move |c| (i, c, channel_range(colors, c))))
+            .max_by_key(|(_, _, range)| *range)
+        else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_unstable_by_key(|c| c.rgb[channel]);
+        let half = colors.iter().map(|c| c.count).sum::<u64>() / 2;
+        let mut cumulative = 0;
+        let split = colors
+            .iter()
+            .position(|c| {
+                cumulative += c.count;
+                cumulative >= half
+            })
+            .unwrap_or(0)
+            .clamp(0, colors.len() - 2)
+            + 1;
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    boxes.iter().map(|colors| weighted_mean(colors)).collect()
+}
+
+#[derive(Default)]
+struct OctreeNode {
+    children: [Option<usize>; 8],
+    is_leaf: bool,
+    count: u64,
+    sum: [u64; 3],
+}
+
+const OCTREE_DEPTH: usize = 8;
+
+fn octree(histogram: &[ColorCount], max_colors: usize) -> Vec<[u8; 3]> {
+    let mut nodes = vec![OctreeNode::default()];
+    let mut reducible: [Vec<usize>; OCTREE_DEPTH] = Default::default();
+    let mut leaf_count = 0;
+    reducible[0].push(0);
+
+    for color in histogram {
+        let mut node = 0;
+        for level in 0..OCTREE_DEPTH {
+            if nodes[node].is_leaf {
+                break;
+            }
+            let bit = 7 - level;
+            let child_index = (((color.rgb[0] >> bit) & 1) << 2
+                | ((color.rgb[1] >> bit) & 1) << 1
+                | ((color.rgb[2] >> bit) & 1)) as usize;
+            node = match nodes[node].children[child_index] {
+                Some(child) => child,
+                None => {
+                    let child = nodes.len();
+                    let is_leaf = level + 1 == OCTREE_DEPTH;
+                    nodes.push(OctreeNode {
+                        is_leaf,
+                        ..Default::default()
+                    });
+                    nodes[node].children[child_index] = Some(child);
+                    if is_leaf {
+                        leaf_count += 1;
+                    } else {
+                        reducible[level + 1].push(child);
+                    }
+                    child
+                }
+            };
+        }
+
+        nodes[node].count += color.count;
+        for c in 0..3 {
+            nodes[node].sum[c] += color.rgb[c] as u64 * color.count;
+        }
+
+        // Fold the deepest branches into their parent until the palette fits again.
+        while leaf_count > max_colors {
+            let Some(node) = reducible.iter_mut().rev().find_map(|level| level.pop()) else {
+                break;
+            };
+            let mut merged_children = 0;
+            let children = nodes[node].children;
+            for child in children.into_iter().flatten() {
+                let (count, sum) = (nodes[child].count, nodes[child].sum);
+                nodes[node].count += count;
+                for (total, value) in nodes[node].sum.iter_mut().zip(sum) {
+                    *total += value;
+                }
+                merged_children += 1;
+            }
+            nodes[node].children = [None; 8];
+            nodes[node].is_leaf = true;
+            leaf_count = leaf_count + 1 - merged_children;
+        }
+    }
+
+    nodes
+        .iter()
+        .filter(|node| node.is_leaf && node.count > 0)
+        .map(|node| node.sum.map(|s| ((s + node.count / 2) / node.count) as u8))
+        .collect()
+}
+
+fn k_means(histogram: &[ColorCount], max_colors: usize) -> Vec<[u8; 3]> {
+    let samples = if histogram.len() > KMEANS_MAX_COLORS {
+        // Bucket similar colours together so each iteration stays fast on photos.
+        let mut buckets: HashMap<[u8; 3], ([u64; 3], u64)> = HashMap::new();
+        for color in histogram {
+            let (sum, count) = buckets.entry(color.rgb.map(|v| v >> 3)).or_default();
+            for (total, value) in sum.iter_mut().zip(color.rgb) {
+                *total += value as u64 * color.count;
+            }
+            *count += color.count;
+        }
+        buckets
+            .into_values()
+            .map(|(sum, count)| ColorCount {
+                rgb: sum.map(|s| (s / count) as u8),
+                count,
+            })
+            .collect()
+    } else {
+        histogram.to_vec()
+    };
+
+    let mut centers: Vec<[f64; 3]> = median_cut(&samples, max_colors)
+        .into_iter()
+        .map(|rgb| rgb.map(|v| v as f64))
+        .collect();
+
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut sums = vec![[0.0f64; 3]; centers.len()];
+        let mut counts = vec![0.0f64; centers.len()];
+        for color in &samples {
+            let rgb = color.rgb.map(|v| v as f64);
+            let nearest = (0..centers.len())
+                .min_by(|a, b| {
+                    distance(&centers[*a], &rgb).total_cmp(&distance(&centers[*b], &rgb))
+                })
+                .unwrap_or(0);
+            for c in 0..3 {
+                sums[nearest][c] += rgb[c] * color.count as f64;
+            }
+            counts[nearest] += color.count as f64;
+        }
+
+        let mut moved = false;
+        for (i, center) in centers.iter_mut().enumerate() {
+            if counts[i] > 0.0 {
+                let updated = sums[i].map(|s| s / counts[i]);
+                moved |= distance(center, &updated) > 0.25;
+                *center = updated;
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+
+    centers
+        .into_iter()
+        .map(|center| center.map(|v| v.round().clamp(0.0, 255.0) as u8))
+        .collect()
+}
+
+fn neuquant(histogram: &[ColorCount], max_colors: usize) -> Vec<[u8; 3]> {
+    // NeuQuant learns from a pixel stream, so expand the histogram back out with a cap
+    // on repeats to keep the training set (and memory use) reasonably sized.
+    let pixels: Vec<u8> = histogram
+        .iter()
+        .flat_map(|color| {
+            let repeats = color.count.min(64) as usize;
+            std::iter::repeat_n([color.rgb[0], color.rgb[1], color.rgb[2], u8::MAX], repeats)
+        })
+        .flatten()
+        .collect();
+    NeuQuant::new(NEUQUANT_SAMPLE_FACTOR, max_colors, &pixels)
+        .color_map_rgb()
+        .chunks_exact(3)
+        .map(|c| [c[0], c[1], c[2]])
+        .collect()
+}
+
+fn distance(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    (0..3).map(|c| (a[c] - b[c]).powi(2)).sum()
+}
@@ -0,0 +1,145 @@
+use clap::ValueEnum;
+use image::RgbaImage;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum DitherMethod {
+    /// Map every pixel straight to its nearest palette colour.
+    None,
+
+    /// Diffuse the error of each pixel to its neighbours using Floyd–Steinberg weights.
+    FloydSteinberg,
+
+    /// Diffuse three quarters of the error of each pixel using Atkinson weights.
+    Atkinson,
+
+    /// Offset each pixel by an 8x8 ordered Bayer threshold matrix.
+    Bayer,
+}
+
+/// The alpha value below which a pixel is mapped to the transparent palette entry.
+pub const ALPHA_CUTOFF: u8 = 128;
+
+const FLOYD_STEINBERG: &[(isize, isize, f32)] = &[
+    (1, 0, 7.0 / 16.0),
+    (-1, 1, 3.0 / 16.0),
+    (0, 1, 5.0 / 16.0),
+    (1, 1, 1.0 / 16.0),
+];
+
+const ATKINSON: &[(isize, isize, f32)] = &[
+    (1, 0, 1.0 / 8.0),
+    (2, 0, 1.0 / 8.0),
+    (-1, 1, 1.0 / 8.0),
+    (0, 1, 1.0 / 8.0),
+    (1, 1, 1.0 / 8.0),
+    (0, 2, 1.0 / 8.0),
+];
+
+const BAYER_8X8: [[u8; 8]; 8] = [
+    [0, 32, 8, 40, 2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44, 4, 36, 14, 46, 6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [3, 35, 11, 43, 1, 33, 9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47, 7, 39, 13, 45, 5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+/// Finds the closest palette entry to a colour, remembering previous answers.
+pub struct NearestColor<'a> {
+    palette: &'a [[u8; 3]],
+    cache: HashMap<[u8; 3], u8>,
+}
+
+impl<'a> NearestColor<'a> {
+    pub fn new(palette: &'a [[u8; 3]]) -> Self {
+        Self {
+            palette,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn index_of(&mut self, rgb: [u8; 3]) -> u8 {
+        let palette = self.palette;
+        *self.cache.entry(rgb).or_insert_with(|| {
+            palette
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, entry)| {
+                    (0..3)
+                        .map(|c| (entry[c] as i32 - rgb[c] as i32).pow(2))
+                        .sum::<i32>()
+                })
+                .map(|(index, _)| index as u8)
+                .unwrap_or(0)
+        })
+    }
+}
+
+impl DitherMethod {
+    /// Map every pixel of an image to an index into the palette. Pixels that are mostly
+    /// transparent are mapped to `transparent_index` when one is given, which must be
+    /// the last entry of the palette so it is never chosen for an opaque pixel.
+    pub fn map_pixels(
+        self,
+        image: &RgbaImage,
+        palette: &[[u8; 3]],
+        transparent_index: Option<u8>,
+    ) -> Vec<u8> {
+        let palette = match transparent_index {
+            Some(transparent) => &palette[..transparent as usize],
+            None => palette,
+        };
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let mut nearest = NearestColor::new(palette);
+        let mut indices = vec![0u8; width * height];
+        let mut working: Vec<[f32; 3]> = image
+            .pixels()
+            .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32])
+            .collect();
+        let bayer_spread = 255.0 / (palette.len() as f32).cbrt();
+
+        for y in 0..height {
+            for x in 0..width {
+                let i = y * width + x;
+                if let Some(transparent) = transparent_index {
+                    if image.as_raw()[i * 4 + 3] < ALPHA_CUTOFF {
+                        indices[i] = transparent;
+                        continue;
+                    }
+                }
+
+                let mut value = working[i];
+                if let Self::Bayer = self {
+                    let offset = (BAYER_8X8[y % 8][x % 8] as f32 + 0.5) / 64.0 - 0.5;
+                    value = value.map(|v| v + offset * bayer_spread);
+                }
+                let rgb = value.map(|v| v.round().clamp(0.0, 255.0) as u8);
+                let index = nearest.index_of(rgb);
+                indices[i] = index;
+
+                let weights = match self {
+                    Self::FloydSteinberg => FLOYD_STEINBERG,
+                    Self::Atkinson => ATKINSON,
+                    Self::None | Self::Bayer => continue,
+                };
+                let error: [f32; 3] =
+                    std::array::from_fn(|c| value[c] - palette[index as usize][c] as f32);
+                for (dx, dy, weight) in weights {
+                    let (nx, ny) = (x as isize + dx, y as isize + dy);
+                    if nx < 0 || nx >= width as isize || ny >= height as isize {
+                        continue;
+                    }
+                    let neighbour = &mut working[ny as usize * width + nx as usize];
+                    for c in 0..3 {
+                        neighbour[c] += error[c] * weight;
+                    }
+                }
+            }
+        }
+
+        indices
+    }
+}
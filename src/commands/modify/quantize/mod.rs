@@ -0,0 +1,195 @@
+mod algorithms;
+mod dither;
+mod palette_file;
+
+pub use self::algorithms::{QuantizeAlgorithm, histogram};
+pub use self::dither::{ALPHA_CUTOFF, DitherMethod};
+pub use self::palette_file::{MAX_PALETTE_SIZE, load_palette};
+
+use crate::commands::ExecutableCommand;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use image::{DynamicImage, ImageFormat, ImageReader, RgbaImage};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// Reduce an image to a limited palette of colours, optionally with dithering.
+///
+/// PNG and GIF outputs are written as true indexed (palette) images. Other formats
+/// are written at their usual colour depth using only the palette's colours.
+/// Pixels that are mostly transparent are given their own fully transparent entry.
+#[derive(Debug, Clone, Parser)]
+pub struct QuantizeCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The maximum number of colours in the generated palette (2 to 256).
+    #[arg(long = "colors", default_value_t = 256, value_parser = clap::value_parser!(u16).range(2..=256))]
+    pub colors: u16,
+
+    /// The algorithm used to generate the palette.
+    #[arg(long = "algorithm", default_value = "median-cut")]
+    pub algorithm: QuantizeAlgorithm,
+
+    /// The dithering applied when mapping pixels to the palette.
+    #[arg(long = "dither", default_value = "none")]
+    pub dither: DitherMethod,
+
+    /// A path on disk to a fixed palette to use instead of generating one.
+    /// GIMP palettes (.gpl), Adobe Color Tables (.act) and swatch images are supported.
+    #[arg(long = "palette", conflicts_with_all = ["colors", "algorithm"])]
+    pub palette_path: Option<PathBuf>,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+impl ExecutableCommand for QuantizeCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?
+            .into_rgba8();
+
+        let has_transparency = image.pixels().any(|p| p[3] < ALPHA_CUTOFF);
+        let mut palette = match &self.palette_path {
+            Some(path) => load_palette(path)?,
+            None => {
+                let max_colors = self.colors as usize - has_transparency as usize;
+                let opaque = image
+                    .pixels()
+                    .filter(|p| p[3] >= ALPHA_CUTOFF)
+                    .map(|p| [p[0], p[1], p[2]]);
+                self.algorithm.build_palette(&histogram(opaque), max_colors)
+            }
+        };
+        if palette.is_empty() {
+            // A completely transparent image still needs one colour to index.
+            palette.push([0; 3]);
+        }
+
+        let transparent_index = if has_transparency {
+            if palette.len() == MAX_PALETTE_SIZE {
+                bail!("the palette has no room left for a transparent entry");
+            }
+            palette.push([0; 3]);
+            Some((palette.len() - 1) as u8)
+        } else {
+            None
+        };
+
+        let indices = self.dither.map_pixels(&image, &palette, transparent_index);
+
+        match output_format {
+            ImageFormat::Png => save_indexed_png(
+                &self.output_path,
+                &image,
+                &palette,
+                &indices,
+                transparent_index,
+            ),
+            ImageFormat::Gif => save_indexed_gif(
+                &self.output_path,
+                &image,
+                &palette,
+                &indices,
+                transparent_index,
+            ),
+            _ => {
+                let expanded = RgbaImage::from_fn(image.width(), image.height(), |x, y| {
+                    let index = indices[(y * image.width() + x) as usize];
+                    let [r, g, b] = palette[index as usize];
+                    let alpha = if Some(index) == transparent_index {
+                        0
+                    } else {
+                        u8::MAX
+                    };
+                    image::Rgba([r, g, b, alpha])
+                });
+                let expanded = match transparent_index {
+                    Some(_) => DynamicImage::ImageRgba8(expanded),
+                    None => DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(expanded).into_rgb8()),
+                };
+                expanded
+                    .save_with_format(&self.output_path, output_format)
+                    .context(ERROR_IMGSAVE_CTX)
+            }
+        }
+    }
+}
+
+fn save_indexed_png(
+    path: &Path,
+    image: &RgbaImage,
+    palette: &[[u8; 3]],
+    indices: &[u8],
+    transparent_index: Option<u8>,
+) -> Result<()> {
+    let file = File::create(path).context(ERROR_IMGSAVE_CTX)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width(), image.height());
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(palette.concat());
+    if let Some(transparent) = transparent_index {
+        // Entries after the last tRNS value are implicitly opaque.
+        let mut trns = vec![u8::MAX; transparent as usize + 1];
+        trns[transparent as usize] = 0;
+        encoder.set_trns(trns);
+    }
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(indices))
+        .context(ERROR_IMGSAVE_CTX)?;
+    Ok(())
+}
+
+fn save_indexed_gif(
+    path: &Path,
+    image: &RgbaImage,
+    palette: &[[u8; 3]],
+    indices: &[u8],
+    transparent_index: Option<u8>,
+) -> Result<()> {
+    let (Ok(width), Ok(height)) = (u16::try_from(image.width()), u16::try_from(image.height()))
+    else {
+        bail!("GIF images can be at most 65535x65535 pixels");
+    };
+    let file = File::create(path).context(ERROR_IMGSAVE_CTX)?;
+    let mut encoder = gif::Encoder::new(BufWriter::new(file), width, height, &palette.concat())
+        .context(ERROR_IMGSAVE_CTX)?;
+    let frame = gif::Frame {
+        width,
+        height,
+        buffer: indices.into(),
+        transparent: transparent_index,
+        ..Default::default()
+    };
+    encoder.write_frame(&frame).context(ERROR_IMGSAVE_CTX)?;
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use crate::commands::messages::{ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX};
+use anyhow::{Context, Result, bail};
+use image::ImageReader;
+use std::fs;
+use std::path::Path;
+
+/// The largest number of entries an indexed PNG or GIF palette can hold.
+pub const MAX_PALETTE_SIZE: usize = 256;
+
+/// Load a fixed palette from a GIMP palette (.gpl), Adobe Color Table (.act)
+/// or an image whose unique colours form the palette (e.g. a PNG swatch).
+pub fn load_palette(path: &Path) -> Result<Vec<[u8; 3]>> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    let palette = match extension.as_deref() {
+        Some("gpl") => {
+            parse_gpl(&fs::read_to_string(path).context("failed to read palette file")?)?
+        }
+        Some("act") => parse_act(&fs::read(path).context("failed to read palette file")?)?,
+        _ => {
+            let image = ImageReader::open(path)
+                .context(ERROR_IMGREAD_CTX)?
+                .decode()
+                .context(ERROR_IMGDECODE_CTX)?
+                .into_rgba8();
+            let mut colors = Vec::new();
+            for pixel in image.pixels().filter(|p| p[3] > 0) {
+                let rgb = [pixel[0], pixel[1], pixel[2]];
+                if !colors.contains(&rgb) {
+                    if colors.len() == MAX_PALETTE_SIZE {
+                        bail!(
+                            "palette images must contain at most {MAX_PALETTE_SIZE} unique colours"
+                        );
+                    }
+                    colors.push(rgb);
+                }
+            }
+            colors
+        }
+    };
+
+    if palette.is_empty() {
+        bail!("the palette file does not contain any colours");
+    }
+    if palette.len() > MAX_PALETTE_SIZE {
+        bail!("palettes can contain at most {MAX_PALETTE_SIZE} colours");
+    }
+    Ok(palette)
+}
+
+fn parse_gpl(contents: &str) -> Result<Vec<[u8; 3]>> {
+    let mut lines = contents.lines();
+    if lines.next().map(str::trim) != Some("GIMP Palette") {
+        bail!("the palette file is missing its 'GIMP Palette' header");
+    }
+
+    let mut colors = Vec::new();
+    for line in lines.map(str::trim) {
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with("Name:")
+            || line.starts_with("Columns:")
+        {
+            continue;
+        }
+        let values = line
+            .split_whitespace()
+            .take(3)
+            .map(|v| v.parse::<u8>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("invalid palette entry '{line}'"))?;
+        match values.as_slice() {
+            [r, g, b] => colors.push([*r, *g, *b]),
+            _ => bail!("invalid palette entry '{line}'"),
+        }
+    }
+    Ok(colors)
+}
+
+fn parse_act(bytes: &[u8]) -> Result<Vec<[u8; 3]>> {
+    const TABLE_SIZE: usize = MAX_PALETTE_SIZE * 3;
+    let count = match bytes.len() {
+        TABLE_SIZE => MAX_PALETTE_SIZE,
+        // Newer files append the number of colours in use and a transparent index.
+        n if n == TABLE_SIZE + 4 => (u16::from_be_bytes([bytes[TABLE_SIZE], bytes[TABLE_SIZE + 1]])
+            as usize)
+            .clamp(1, MAX_PALETTE_SIZE),
+        _ => bail!("Adobe Color Table files must be 768 or 772 bytes long"),
+    };
+    Ok(bytes[..count * 3]
+        .chunks_exact(3)
+        .map(|c| [c[0], c[1], c[2]])
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_gpl_skipping_metadata() {
+        let contents = "GIMP Palette\nName: Test\nColumns: 2\n# Comment\n\n  0   0   0\tBlack\n255 128  64 Orange\n";
+        assert_eq!(
+            parse_gpl(contents).unwrap(),
+            vec![[0, 0, 0], [255, 128, 64]]
+        );
+    }
+
+    #[test]
+    fn rejects_gpl_without_header() {
+        assert!(parse_gpl("0 0 0\n").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_gpl_entries() {
+        assert!(parse_gpl("GIMP Palette\n0 0\n").is_err());
+        assert!(parse_gpl("GIMP Palette\n0 0 256\n").is_err());
+    }
+
+    #[test]
+    fn parses_full_act_table() {
+        let bytes: Vec<u8> = (0..MAX_PALETTE_SIZE * 3).map(|i| (i % 256) as u8).collect();
+        let palette = parse_act(&bytes).unwrap();
+        assert_eq!(palette.len(), MAX_PALETTE_SIZE);
+        assert_eq!(palette[1], [3, 4, 5]);
+    }
+
+    #[test]
+    fn parses_act_colour_count() {
+        let mut bytes = vec![0; MAX_PALETTE_SIZE * 3];
+        bytes[3..6].copy_from_slice(&[10, 20, 30]);
+        bytes.extend_from_slice(&[0, 2, 0xFF, 0xFF]);
+        assert_eq!(parse_act(&bytes).unwrap(), vec![[0, 0, 0], [10, 20, 30]]);
+    }
+
+    #[test]
+    fn rejects_act_with_wrong_length() {
+        assert!(parse_act(&[0; 100]).is_err());
+    }
+}
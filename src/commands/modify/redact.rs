@@ -0,0 +1,276 @@
+use super::draw::raster::rasterize;
+use super::edges::gaussian_blur;
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::{Region, into_color_type, layered_color, parse_color, parse_region};
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use ab_glyph_rasterizer::point;
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::{ColorType, DynamicImage, ImageFormat, ImageReader, Rgba, Rgba32FImage};
+use std::path::PathBuf;
+
+/// Irreversibly redact areas of an image by pixelating, blurring or filling them.
+///
+/// The output is encoded from the redacted pixels alone, so metadata such as EXIF
+/// (which may contain locations, device details or thumbnails) is always stripped.
+#[derive(Debug, Clone, Parser)]
+pub struct RedactCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// A rectangle to redact, formatted as 'x,y,width,height'. Can be given multiple times.
+    #[arg(long = "region", value_parser = parse_region)]
+    pub regions: Vec<Region>,
+
+    /// A polygon to redact, formatted as 'x1,y1,x2,y2,x3,y3,...'. Can be given multiple times.
+    #[arg(long = "polygon", value_parser = parse_polygon)]
+    pub polygons: Vec<Polygon>,
+
+    /// How the redacted areas are obscured.
+    #[arg(long = "mode", default_value = "pixelate")]
+    pub mode: RedactMode,
+
+    /// The size in pixels of the blocks used by pixelation (2 to 512).
+    #[arg(
+        long = "block-size",
+        default_value_t = 16,
+        value_parser = clap::value_parser!(u32).range(2..=512)
+    )]
+    pub block_size: u32,
+
+    /// How strongly blurred areas are blurred (aka. sigma value).
+    #[arg(long = "strength", default_value_t = 12.0)]
+    pub strength: f32,
+
+    /// The colour used by the fill mode, formatted as hex.
+    #[arg(long = "color", default_value = "#000000", value_parser = parse_color)]
+    pub color: Rgba<u8>,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum RedactMode {
+    /// Replace the area with large blocks of its average colours.
+    Pixelate,
+
+    /// Blur the area. It is pixelated first so the blur cannot be reversed.
+    Blur,
+
+    /// Paint over the area with a solid colour.
+    Fill,
+}
+
+#[derive(Debug, Clone)]
+pub struct Polygon(Vec<[f32; 2]>);
+
+fn parse_polygon(value: &str) -> Result<Polygon, String> {
+    let numbers = value
+        .split(',')
+        .map(|v| v.trim().parse::<f32>().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if numbers.len() < 6 || numbers.len() % 2 != 0 {
+        return Err("expected a polygon formatted as 'x1,y1,x2,y2,x3,y3,...'".to_string());
+    }
+    Ok(Polygon(
+        numbers.chunks_exact(2).map(|p| [p[0], p[1]]).collect(),
+    ))
+}
+
+/// An area of the image to redact: its bounding box and which pixels inside it are covered.
+struct Area {
+    region: Region,
+    covered: Vec<bool>,
+}
+
+impl Area {
+    fn contains(&self, x: u32, y: u32) -> bool {
+        self.covered[((y - self.region.y) * self.region.width + (x - self.region.x)) as usize]
+    }
+
+    fn pixels(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let Region {
+            x,
+            y,
+            width,
+            height,
+        } = self.region;
+        (y..y + height)
+            .flat_map(move |py| (x..x + width).map(move |px| (px, py)))
+            .filter(|(px, py)| self.contains(*px, *py))
+    }
+}
+
+impl ExecutableCommand for RedactCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+        if self.regions.is_empty() && self.polygons.is_empty() {
+            bail!("at least one region or polygon to redact must be given");
+        }
+        if self.strength <= 0.0 {
+            bail!("strength must be greater than 0");
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        let color = match self.mode {
+            RedactMode::Fill
+                if self.color[0] != self.color[1] || self.color[1] != self.color[2] =>
+            {
+                layered_color(image.color(), ColorType::Rgba8)
+            }
+            _ => image.color(),
+        };
+        let mut buffer = image.into_rgba32f();
+        let bounds = buffer.dimensions();
+
+        // Refuse to continue if anything asked to be redacted would be missed.
+        let mut areas = Vec::new();
+        for region in &self.regions {
+            let x1 = region.x.saturating_add(region.width).min(bounds.0);
+            let y1 = region.y.saturating_add(region.height).min(bounds.1);
+            if region.width == 0 || region.height == 0 || region.x >= x1 || region.y >= y1 {
+                bail!(
+                    "the region {},{},{},{} is outside the bounds of the image",
+                    region.x,
+                    region.y,
+                    region.width,
+                    region.height
+                );
+            }
+            let region = Region {
+                width: x1 - region.x,
+                height: y1 - region.y,
+                ..*region
+            };
+            areas.push(Area {
+                region,
+                covered: vec![true; (region.width * region.height) as usize],
+            });
+        }
+        for polygon in &self.polygons {
+            let points = polygon.0.iter().map(|p| point(p[0], p[1])).collect();
+            let Some((mask, (x, y))) = rasterize(&[points], bounds) else {
+                bail!("a polygon is outside the bounds of the image");
+            };
+            // Treat partially covered edge pixels as fully covered so nothing leaks through.
+            areas.push(Area {
+                region: Region {
+                    x: x as u32,
+                    y: y as u32,
+                    width: mask.width(),
+                    height: mask.height(),
+                },
+                covered: mask.pixels().map(|p| p[0] > 0.0).collect(),
+            });
+        }
+
+        for area in &areas {
+            match self.mode {
+                RedactMode::Pixelate => pixelate(&mut buffer, area, self.block_size),
+                RedactMode::Blur => {
+                    pixelate(&mut buffer, area, (self.strength.ceil() as u32).max(2));
+                    blur(&mut buffer, area, self.strength);
+                }
+                RedactMode::Fill => {
+                    let fill = Rgba(self.color.0.map(|c| c as f32 / 255.0));
+                    for (x, y) in area.pixels() {
+                        buffer.put_pixel(x, y, fill);
+                    }
+                }
+            }
+        }
+
+        into_color_type(DynamicImage::ImageRgba32F(buffer), color)
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
+
+/// Replace the covered pixels of each block of the area with their average colour.
+fn pixelate(buffer: &mut Rgba32FImage, area: &Area, block_size: u32) {
+    let Region {
+        x,
+        y,
+        width,
+        height,
+    } = area.region;
+    for block_y in (y..y + height).step_by(block_size as usize) {
+        for block_x in (x..x + width).step_by(block_size as usize) {
+            let pixels: Vec<(u32, u32)> = (block_y..(block_y + block_size).min(y + height))
+                .flat_map(|py| {
+                    (block_x..(block_x + block_size).min(x + width)).map(move |px| (px, py))
+                })
+                .filter(|(px, py)| area.contains(*px, *py))
+                .collect();
+            if pixels.is_empty() {
+                continue;
+            }
+
+            let mut sum = [0.0f64; 4];
+            for (px, py) in &pixels {
+                for (total, value) in sum.iter_mut().zip(buffer.get_pixel(*px, *py).0) {
+                    *total += value as f64;
+                }
+            }
+            let average = Rgba(sum.map(|v| (v / pixels.len() as f64) as f32));
+            for (px, py) in pixels {
+                buffer.put_pixel(px, py, average);
+            }
+        }
+    }
+}
+
+/// Gaussian blur the covered pixels of the area, sampling from its surroundings too.
+fn blur(buffer: &mut Rgba32FImage, area: &Area, sigma: f32) {
+    let margin = (sigma * 3.0).ceil() as u32;
+    let Region {
+        x,
+        y,
+        width,
+        height,
+    } = area.region;
+    let x0 = x.saturating_sub(margin);
+    let y0 = y.saturating_sub(margin);
+    let x1 = (x + width + margin).min(buffer.width());
+    let y1 = (y + height + margin).min(buffer.height());
+    let (window_width, window_height) = ((x1 - x0) as usize, (y1 - y0) as usize);
+
+    for channel in 0..4 {
+        let plane: Vec<f32> = (y0..y1)
+            .flat_map(|py| (x0..x1).map(move |px| (px, py)))
+            .map(|(px, py)| buffer.get_pixel(px, py)[channel])
+            .collect();
+        let blurred = gaussian_blur(&plane, window_width, window_height, sigma);
+        for (px, py) in area.pixels() {
+            let index = (py - y0) as usize * window_width + (px - x0) as usize;
+            buffer.get_pixel_mut(px, py)[channel] = blurred[index];
+        }
+    }
+}
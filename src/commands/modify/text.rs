@@ -0,0 +1,415 @@
+use super::composite::{Mask, paint};
+use super::edges::gaussian_blur;
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::{
+    Gravity, Region, into_color_type, layered_color, parse_color, parse_offset, parse_region,
+};
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use ab_glyph::{Font, FontVec, PxScale, ScaleFont, point};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::{ColorType, DynamicImage, ImageFormat, ImageReader, Rgba};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Render text onto an image.
+///
+/// The text may contain template variables that are replaced before rendering:
+/// {filename}, {stem}, {width}, {height}, and the EXIF capture {date} and {datetime}.
+/// Images without an EXIF capture date use the time the file was last modified, in UTC.
+#[derive(Debug, Clone, Parser)]
+pub struct TextCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The text to render. Use '\n' for line breaks.
+    #[arg(long = "text")]
+    pub text: String,
+
+    /// A path on disk to the TrueType or OpenType font to render the text with.
+    #[arg(long = "font")]
+    pub font_path: PathBuf,
+
+    /// The height of the text in pixels.
+    #[arg(long = "size", default_value_t = 32.0)]
+    pub size: f32,
+
+    /// The colour of the text, formatted as hex.
+    #[arg(long = "color", default_value = "#ffffff", value_parser = parse_color)]
+    pub color: Rgba<u8>,
+
+    /// The width in pixels of the outline drawn around the text.
+    #[arg(long = "stroke-width", default_value_t = 0.0)]
+    pub stroke_width: f32,
+
+    /// The colour of the outline, formatted as hex.
+    #[arg(long = "stroke-color", default_value = "#000000", value_parser = parse_color)]
+    pub stroke_color: Rgba<u8>,
+
+    /// Draw a drop shadow offset from the text by this many pixels, formatted as 'x,y'.
+    #[arg(long = "shadow-offset", allow_hyphen_values = true, value_parser = parse_offset)]
+    pub shadow_offset: Option<(i32, i32)>,
+
+    /// The colour of the drop shadow, formatted as hex.
+    #[arg(long = "shadow-color", default_value = "#00000080", value_parser = parse_color)]
+    pub shadow_color: Rgba<u8>,
+
+    /// How strongly the drop shadow is blurred (aka. sigma value).
+    #[arg(long = "shadow-blur", default_value_t = 0.0)]
+    pub shadow_blur: f32,
+
+    /// How lines are aligned relative to each other.
+    #[arg(long = "align", default_value = "left")]
+    pub align: TextAlign,
+
+    /// The area to place the text in, formatted as 'x,y,width,height'.
+    /// Lines longer than the area are wrapped. Defaults to the whole image.
+    #[arg(long = "box", value_parser = parse_region)]
+    pub area: Option<Region>,
+
+    /// Where the text is anchored within its area.
+    #[arg(long = "gravity", default_value = "north-west")]
+    pub gravity: Gravity,
+
+    /// How far in pixels to move the text inwards from the edges it is anchored to,
+    /// formatted as 'x,y'.
+    #[arg(long = "offset", default_value = "0,0", allow_hyphen_values = true, value_parser = parse_offset)]
+    pub offset: (i32, i32),
+
+    /// The spacing between lines, as a multiple of the font's line height.
+    #[arg(long = "line-spacing", default_value_t = 1.0)]
+    pub line_spacing: f32,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum TextAlign {
+    /// Align lines to the left edge of the text.
+    Left,
+
+    /// Centre lines within the text.
+    Center,
+
+    /// Align lines to the right edge of the text.
+    Right,
+}
+
+impl ExecutableCommand for TextCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() || !self.font_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() || !self.font_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+        if self.size <= 0.0 {
+            bail!("size must be greater than 0");
+        }
+        if self.stroke_width < 0.0 || self.shadow_blur < 0.0 {
+            bail!("stroke width and shadow blur must not be negative");
+        }
+
+        let font = FontVec::try_from_vec(fs::read(&self.font_path).context("failed to read font")?)
+            .context("failed to parse font")?;
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(&self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+
+        let text = expand_template(&self.text, &self.input_path, &image)?;
+        if text.trim().is_empty() {
+            bail!("the text must not be empty");
+        }
+        let area = self.area.unwrap_or(Region {
+            x: 0,
+            y: 0,
+            width: image.width(),
+            height: image.height(),
+        });
+
+        let uses_color = [self.color, self.stroke_color, self.shadow_color]
+            .iter()
+            .any(|c| c[0] != c[1] || c[1] != c[2]);
+        let color = if uses_color {
+            layered_color(image.color(), ColorType::Rgba8)
+        } else {
+            image.color()
+        };
+        let mut buffer = image.into_rgba32f();
+
+        // Render the text with enough margin around it for the stroke and shadow.
+        let margin = (self.stroke_width + self.shadow_blur * 3.0).ceil() as u32 + 1;
+        let fill = render(
+            &font,
+            &text,
+            self.size,
+            area.width,
+            self.line_spacing,
+            self.align,
+            margin,
+        );
+        let block = (fill.width() - 2 * margin, fill.height() - 2 * margin);
+        let (x, y) = self
+            .gravity
+            .position((area.width, area.height), block, self.offset);
+        let origin = (
+            area.x as i64 + x - margin as i64,
+            area.y as i64 + y - margin as i64,
+        );
+
+        let outline = if self.stroke_width > 0.0 {
+            dilate(&fill, self.stroke_width)
+        } else {
+            fill.clone()
+        };
+        if let Some((shadow_x, shadow_y)) = self.shadow_offset {
+            let mut shadow = outline.clone();
+            if self.shadow_blur > 0.0 {
+                let (width, height) = (shadow.width() as usize, shadow.height() as usize);
+                let blurred = gaussian_blur(shadow.as_raw(), width, height, self.shadow_blur);
+                shadow = Mask::from_raw(width as u32, height as u32, blurred)
+                    .context("shadow does not match the text dimensions")?;
+            }
+            let position = (origin.0 + shadow_x as i64, origin.1 + shadow_y as i64);
+            paint(&mut buffer, &shadow, position, self.shadow_color);
+        }
+        if self.stroke_width > 0.0 {
+            paint(&mut buffer, &outline, origin, self.stroke_color);
+        }
+        paint(&mut buffer, &fill, origin, self.color);
+
+        into_color_type(DynamicImage::ImageRgba32F(buffer), color)
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
+
+/// Replace the `{variable}` placeholders in a template. Use `{{` and `}}` for literal braces.
+fn expand_template(template: &str, path: &Path, image: &DynamicImage) -> Result<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        output.push_str(&rest[..start]);
+        let brace = &rest[start..];
+        if brace.starts_with("{{") || brace.starts_with("}}") {
+            output.push_str(&brace[..1]);
+            rest = &brace[2..];
+            continue;
+        }
+        let Some(end) = brace.find('}').filter(|_| brace.starts_with('{')) else {
+            bail!("unmatched brace in text template");
+        };
+        let value = match &brace[1..end] {
+            "filename" => file_name(path.file_name()),
+            "stem" => file_name(path.file_stem()),
+            "width" => image.width().to_string(),
+            "height" => image.height().to_string(),
+            "date" => capture_date(path)?[..10].to_string(),
+            "datetime" => capture_date(path)?,
+            other => bail!("unknown template variable '{{{}}}'", other),
+        };
+        output.push_str(&value);
+        rest = &brace[end + 1..];
+    }
+    output.push_str(rest);
+    Ok(output.replace("\\n", "\n"))
+}
+
+fn file_name(name: Option<&std::ffi::OsStr>) -> String {
+    name.map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Get the date the image was captured, formatted as 'YYYY-MM-DD HH:MM:SS'.
+fn capture_date(path: &Path) -> Result<String> {
+    if let Some(date) = exif_date(path) {
+        return Ok(date);
+    }
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .context("failed to read the file's modification time")?;
+    let seconds = match modified.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs() as i64,
+        Err(err) => -(err.duration().as_secs() as i64),
+    };
+
+    // Convert days since the Unix epoch to a civil date, using 400-year eras that
+    // start on the 1st of March so leap days fall at the end of each year.
+    let (days, time) = (seconds.div_euclid(86400), seconds.rem_euclid(86400));
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    Ok(format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    ))
+}
+
+/// Read the date the image was captured from its EXIF metadata, formatted as 'YYYY-MM-DD HH:MM:SS'.
+fn exif_date(path: &Path) -> Option<String> {
+    let exif = exif::Reader::new()
+        .read_from_container(&mut BufReader::new(File::open(path).ok()?))
+        .ok()?;
+    let date = [exif::Tag::DateTimeOriginal, exif::Tag::DateTime]
+        .into_iter()
+        .filter_map(|tag| exif.get_field(tag, exif::In::PRIMARY))
+        .find_map(|field| match &field.value {
+            exif::Value::Ascii(values) => exif::DateTime::from_ascii(values.first()?).ok(),
+            _ => None,
+        })?;
+    Some(format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        date.year, date.month, date.day, date.hour, date.minute, date.second
+    ))
+}
+
+/// Render text into a coverage mask, wrapping lines to fit `max_width` and
+/// surrounding the text with `margin` empty pixels.
+fn render(
+    font: &FontVec,
+    text: &str,
+    size: f32,
+    max_width: u32,
+    line_spacing: f32,
+    align: TextAlign,
+    margin: u32,
+) -> Mask {
+    let font = font.as_scaled(PxScale::from(size));
+    let measure = |line: &str| {
+        let mut width = 0.0;
+        let mut previous = None;
+        for c in line.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                width += font.kern(previous, id);
+            }
+            width += font.h_advance(id);
+            previous = Some(id);
+        }
+        width
+    };
+
+    // Greedily wrap words onto lines, keeping explicit line breaks.
+    let mut lines: Vec<(String, f32)> = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{line} {word}")
+            };
+            if measure(&candidate) > max_width as f32 && !line.is_empty() {
+                let width = measure(&line);
+                lines.push((std::mem::replace(&mut line, word.to_string()), width));
+            } else {
+                line = candidate;
+            }
+        }
+        let width = measure(&line);
+        lines.push((line, width));
+    }
+
+    let line_height = (font.height() + font.line_gap()) * line_spacing;
+    let block_width = lines.iter().map(|(_, w)| *w).fold(0.0, f32::max).ceil() as u32;
+    let block_height = (line_height * (lines.len() - 1) as f32 + font.height()).ceil() as u32;
+    let mut mask = Mask::new(block_width + 2 * margin, block_height + 2 * margin);
+
+    for (index, (line, width)) in lines.iter().enumerate() {
+        let mut x = margin as f32
+            + match align {
+                TextAlign::Left => 0.0,
+                TextAlign::Center => (block_width as f32 - width) / 2.0,
+                TextAlign::Right => block_width as f32 - width,
+            };
+        let baseline = margin as f32 + font.ascent() + line_height * index as f32;
+        let mut previous = None;
+        for c in line.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                x += font.kern(previous, id);
+            }
+            let glyph = id.with_scale_and_position(font.scale(), point(x, baseline));
+            x += font.h_advance(id);
+            previous = Some(id);
+
+            let Some(outlined) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                let (px, py) = (
+                    bounds.min.x as i64 + gx as i64,
+                    bounds.min.y as i64 + gy as i64,
+                );
+                if px >= 0 && py >= 0 && px < mask.width() as i64 && py < mask.height() as i64 {
+                    let pixel = mask.get_pixel_mut(px as u32, py as u32);
+                    pixel[0] = (pixel[0] + coverage).min(1.0);
+                }
+            });
+        }
+    }
+    mask
+}
+
+/// Grow a coverage mask outwards by `radius` pixels with an anti-aliased edge.
+fn dilate(mask: &Mask, radius: f32) -> Mask {
+    let reach = radius.ceil() as i64 + 1;
+    let offsets: Vec<(i64, i64, f32)> = (-reach..=reach)
+        .flat_map(|y| (-reach..=reach).map(move |x| (x, y)))
+        .filter_map(|(x, y)| {
+            let weight = (radius + 0.5 - ((x * x + y * y) as f32).sqrt()).clamp(0.0, 1.0);
+            (weight > 0.0).then_some((x, y, weight))
+        })
+        .collect();
+    let (width, height) = (mask.width() as i64, mask.height() as i64);
+    Mask::from_fn(mask.width(), mask.height(), |x, y| {
+        let mut value: f32 = 0.0;
+        for (dx, dy, weight) in &offsets {
+            let (sx, sy) = (x as i64 + dx, y as i64 + dy);
+            if sx >= 0 && sy >= 0 && sx < width && sy < height {
+                value = value.max(mask.get_pixel(sx as u32, sy as u32)[0] * weight);
+            }
+        }
+        image::Luma([value])
+    })
+}
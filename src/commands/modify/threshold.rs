@@ -0,0 +1,202 @@
+use crate::commands::ExecutableCommand;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::{GrayImage, ImageFormat, ImageReader, Luma};
+use std::path::PathBuf;
+
+/// Convert an image to pure black and white using a global or local threshold.
+#[derive(Debug, Clone, Parser)]
+pub struct ThresholdCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The method used to decide whether each pixel becomes black or white.
+    #[arg(long = "method", default_value = "fixed")]
+    pub method: ThresholdMethod,
+
+    /// The luminance value (0 to 255) at or above which pixels become white.
+    /// Only used by the 'fixed' method.
+    #[arg(long = "value", default_value_t = 128)]
+    pub value: u8,
+
+    /// The side length in pixels of the neighbourhood used by local methods.
+    #[arg(long = "window", default_value_t = 15, value_parser = clap::value_parser!(u32).range(3..))]
+    pub window: u32,
+
+    /// For 'adaptive-mean', the amount subtracted from the local mean.
+    /// For 'sauvola', the sensitivity to local contrast (commonly 0.2 to 0.5).
+    #[arg(long = "k", allow_hyphen_values = true)]
+    pub k: Option<f32>,
+
+    /// Swap black and white in the output.
+    #[arg(long = "invert", default_value_t = false)]
+    pub invert: bool,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ThresholdMethod {
+    /// A single threshold given by --value.
+    Fixed,
+
+    /// A single threshold chosen automatically by Otsu's method.
+    Otsu,
+
+    /// A per-pixel threshold of the local mean minus k.
+    AdaptiveMean,
+
+    /// A per-pixel threshold by Sauvola's method, suited to unevenly lit documents.
+    Sauvola,
+}
+
+const ADAPTIVE_MEAN_DEFAULT_K: f32 = 5.0;
+const SAUVOLA_DEFAULT_K: f32 = 0.34;
+
+/// The dynamic range of the standard deviation for 8-bit images, used by Sauvola's method.
+const SAUVOLA_RANGE: f64 = 128.0;
+
+impl ExecutableCommand for ThresholdCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?
+            .into_luma8();
+
+        let mut output = match self.method {
+            ThresholdMethod::Fixed => global_threshold(&image, self.value),
+            ThresholdMethod::Otsu => global_threshold(&image, otsu_level(&image)),
+            ThresholdMethod::AdaptiveMean => {
+                let k = self.k.unwrap_or(ADAPTIVE_MEAN_DEFAULT_K) as f64;
+                local_threshold(&image, self.window, |mean, _| mean - k)
+            }
+            ThresholdMethod::Sauvola => {
+                let k = self.k.unwrap_or(SAUVOLA_DEFAULT_K) as f64;
+                local_threshold(&image, self.window, |mean, stddev| {
+                    mean * (1.0 + k * (stddev / SAUVOLA_RANGE - 1.0))
+                })
+            }
+        };
+
+        if self.invert {
+            image::imageops::invert(&mut output);
+        }
+        output
+            .save_with_format(self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
+
+fn global_threshold(image: &GrayImage, level: u8) -> GrayImage {
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        binarise(image.get_pixel(x, y)[0] as f64 >= level as f64)
+    })
+}
+
+/// Find the threshold that maximises the variance between the two resulting classes.
+fn otsu_level(image: &GrayImage) -> u8 {
+    let mut histogram = [0u64; 256];
+    for pixel in image.pixels() {
+        histogram[pixel[0] as usize] += 1;
+    }
+
+    let total = image.pixels().len() as f64;
+    let sum_all: f64 = (0..256).map(|i| i as f64 * histogram[i] as f64).sum();
+    let (mut sum_background, mut weight_background) = (0.0, 0.0);
+    let (mut best_level, mut best_variance) = (0, 0.0);
+
+    for (level, count) in histogram.iter().enumerate() {
+        weight_background += *count as f64;
+        if weight_background == 0.0 {
+            continue;
+        }
+        let weight_foreground = total - weight_background;
+        if weight_foreground == 0.0 {
+            break;
+        }
+
+        sum_background += level as f64 * *count as f64;
+        let mean_background = sum_background / weight_background;
+        let mean_foreground = (sum_all - sum_background) / weight_foreground;
+        let variance =
+            weight_background * weight_foreground * (mean_background - mean_foreground).powi(2);
+        if variance > best_variance {
+            best_variance = variance;
+            best_level = level;
+        }
+    }
+
+    // Pixels at the chosen level belong to the background class, so white starts one above it.
+    (best_level + 1).min(u8::MAX as usize) as u8
+}
+
+/// Threshold each pixel against a value computed from the mean and standard deviation of
+/// its neighbourhood, using summed-area tables so the window size does not affect speed.
+fn local_threshold(
+    image: &GrayImage,
+    window: u32,
+    threshold: impl Fn(f64, f64) -> f64,
+) -> GrayImage {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let stride = width + 1;
+    let mut sums = vec![0.0f64; stride * (height + 1)];
+    let mut squares = vec![0.0f64; stride * (height + 1)];
+    for y in 0..height {
+        let (mut row_sum, mut row_squares) = (0.0, 0.0);
+        for x in 0..width {
+            let value = image.get_pixel(x as u32, y as u32)[0] as f64;
+            row_sum += value;
+            row_squares += value * value;
+            sums[(y + 1) * stride + x + 1] = sums[y * stride + x + 1] + row_sum;
+            squares[(y + 1) * stride + x + 1] = squares[y * stride + x + 1] + row_squares;
+        }
+    }
+
+    let half = (window / 2) as usize;
+    GrayImage::from_fn(image.width(), image.height(), |x, y| {
+        let (x, y) = (x as usize, y as usize);
+        let (x0, y0) = (x.saturating_sub(half), y.saturating_sub(half));
+        let (x1, y1) = ((x + half + 1).min(width), (y + half + 1).min(height));
+        let area = ((x1 - x0) * (y1 - y0)) as f64;
+        let region = |table: &[f64]| {
+            table[y1 * stride + x1] - table[y0 * stride + x1] - table[y1 * stride + x0]
+                + table[y0 * stride + x0]
+        };
+        let mean = region(&sums) / area;
+        let variance = (region(&squares) / area - mean * mean).max(0.0);
+        let value = image.get_pixel(x as u32, y as u32)[0] as f64;
+        binarise(value > threshold(mean, variance.sqrt()))
+    })
+}
+
+fn binarise(white: bool) -> Luma<u8> {
+    Luma([if white { u8::MAX } else { 0 }])
+}
@@ -0,0 +1,219 @@
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::{Region, into_color_type, parse_point, parse_region};
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::{ArgGroup, Parser, ValueEnum};
+use image::{DynamicImage, ImageFormat, ImageReader, Rgba32FImage};
+use std::path::PathBuf;
+
+/// Correct an image's white balance so that neutral colours appear neutral.
+#[derive(Debug, Clone, Parser)]
+#[command(group(ArgGroup::new("mode").required(true)))]
+pub struct WhiteBalanceCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A path on disk to where the output image should be placed.
+    /// The image will automatically converted to file type of the
+    /// file extension if possible.
+    #[arg(short = 'o', long = "output")]
+    pub output_path: PathBuf,
+
+    /// The colour temperature in Kelvin of the light the image was taken under (2000 to 15000).
+    /// Lower values cool the image down and higher values warm it up.
+    #[arg(
+        long = "temperature",
+        group = "mode",
+        value_parser = clap::value_parser!(u32).range(2000..=15000)
+    )]
+    pub temperature: Option<u32>,
+
+    /// A green/magenta tint shift applied alongside the temperature (-100 to 100).
+    /// Positive values shift towards magenta and negative values towards green.
+    #[arg(
+        long = "tint",
+        requires = "temperature",
+        allow_hyphen_values = true,
+        value_parser = clap::value_parser!(i32).range(-100..=100)
+    )]
+    pub tint: Option<i32>,
+
+    /// The coordinates of a pixel that should become neutral, formatted as 'x,y'.
+    #[arg(long = "reference", group = "mode", value_parser = parse_point)]
+    pub reference: Option<(u32, u32)>,
+
+    /// A region whose average colour should become neutral, formatted as 'x,y,width,height'.
+    #[arg(long = "region", group = "mode", value_parser = parse_region)]
+    pub region: Option<Region>,
+
+    /// Automatically estimate the correction from the image contents.
+    #[arg(long = "auto", group = "mode")]
+    pub auto: Option<AutoWhiteBalance>,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum AutoWhiteBalance {
+    /// Assume the average colour of the image is neutral grey.
+    GrayWorld,
+
+    /// Assume the brightest colours of the image are white.
+    WhitePatch,
+}
+
+/// The percentile used to find the "white" of each channel, ignoring specular outliers.
+const WHITE_PATCH_PERCENTILE: f32 = 0.99;
+
+/// Rec. 709 luma weights used to keep the overall brightness of the image unchanged.
+const LUMA_WEIGHTS: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+impl ExecutableCommand for WhiteBalanceCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if self.output_path.exists() && !self.overwrite {
+            bail!(OUTPUT_ALREADY_EXISTS);
+        }
+
+        let output_format =
+            ImageFormat::from_path(&self.output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+        let image = ImageReader::open(self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        let color = image.color();
+        let mut buffer = image.into_rgba32f();
+
+        let gains = if let Some(temperature) = self.temperature {
+            let light = kelvin_to_rgb(temperature as f32);
+            let daylight = kelvin_to_rgb(6500.0);
+            let mut gains: [f32; 3] = std::array::from_fn(|c| daylight[c] / light[c]);
+            gains[1] *= 1.0 - self.tint.unwrap_or(0) as f32 / 200.0;
+            gains
+        } else if let Some((x, y)) = self.reference {
+            neutral_gains(average_color(
+                &buffer,
+                Region {
+                    x,
+                    y,
+                    width: 1,
+                    height: 1,
+                },
+            )?)?
+        } else if let Some(region) = self.region {
+            neutral_gains(average_color(&buffer, region)?)?
+        } else {
+            match self.auto.context("no white balance mode was specified")? {
+                AutoWhiteBalance::GrayWorld => neutral_gains(average_color(
+                    &buffer,
+                    Region {
+                        x: 0,
+                        y: 0,
+                        width: buffer.width(),
+                        height: buffer.height(),
+                    },
+                )?)?,
+                AutoWhiteBalance::WhitePatch => neutral_gains(white_patch(&buffer))?,
+            }
+        };
+        let gains = preserve_brightness(gains);
+
+        println!(
+            "Correction gains: R {:.4}, G {:.4}, B {:.4}",
+            gains[0], gains[1], gains[2]
+        );
+
+        for pixel in buffer.pixels_mut() {
+            for c in 0..3 {
+                pixel[c] *= gains[c];
+            }
+        }
+
+        into_color_type(DynamicImage::ImageRgba32F(buffer), color)
+            .save_with_format(&self.output_path, output_format)
+            .context(ERROR_IMGSAVE_CTX)?;
+
+        Ok(())
+    }
+}
+
+/// Approximate the RGB colour of a black-body radiator at the given temperature.
+/// Based on Tanner Helland's curve fit of the CIE 1964 colour matching functions.
+fn kelvin_to_rgb(kelvin: f32) -> [f32; 3] {
+    let t = kelvin / 100.0;
+    let r = if t <= 66.0 {
+        255.0
+    } else {
+        329.69873 * (t - 60.0).powf(-0.13320476)
+    };
+    let g = if t <= 66.0 {
+        99.4708 * t.ln() - 161.11957
+    } else {
+        288.12216 * (t - 60.0).powf(-0.075514846)
+    };
+    let b = if t >= 66.0 {
+        255.0
+    } else {
+        138.51773 * (t - 10.0).ln() - 305.0448
+    };
+    [r, g, b].map(|v| v.clamp(1.0, 255.0) / 255.0)
+}
+
+fn average_color(buffer: &Rgba32FImage, region: Region) -> Result<[f32; 3]> {
+    let Region {
+        x,
+        y,
+        width,
+        height,
+    } = region;
+    if width == 0
+        || height == 0
+        || x.saturating_add(width) > buffer.width()
+        || y.saturating_add(height) > buffer.height()
+    {
+        bail!("the reference region must be inside the bounds of the image");
+    }
+
+    let mut sum = [0.0f64; 3];
+    for py in y..y + height {
+        for px in x..x + width {
+            let pixel = buffer.get_pixel(px, py);
+            for c in 0..3 {
+                sum[c] += pixel[c] as f64;
+            }
+        }
+    }
+    let count = (width as f64) * (height as f64);
+    Ok(sum.map(|v| (v / count) as f32))
+}
+
+fn white_patch(buffer: &Rgba32FImage) -> [f32; 3] {
+    std::array::from_fn(|c| {
+        let mut values: Vec<f32> = buffer.pixels().map(|p| p[c]).collect();
+        let index = ((values.len() - 1) as f32 * WHITE_PATCH_PERCENTILE) as usize;
+        *values.select_nth_unstable_by(index, f32::total_cmp).1
+    })
+}
+
+fn neutral_gains(neutral: [f32; 3]) -> Result<[f32; 3]> {
+    if neutral.iter().any(|v| *v <= 0.0) {
+        bail!("the reference colour must not contain a completely dark channel");
+    }
+    Ok(neutral.map(|v| 1.0 / v))
+}
+
+fn preserve_brightness(gains: [f32; 3]) -> [f32; 3] {
+    let luma: f32 = (0..3).map(|c| gains[c] * LUMA_WEIGHTS[c]).sum();
+    gains.map(|g| g / luma)
+}
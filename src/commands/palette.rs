@@ -0,0 +1,246 @@
+use crate::commands::ExecutableCommand;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use crate::commands::modify::quantize::{ALPHA_CUTOFF, QuantizeAlgorithm, histogram};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::{ImageFormat, ImageReader, Rgb, RgbImage};
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Extract the dominant, average and accent colours of an image.
+#[derive(Debug, Clone, Parser)]
+pub struct PaletteCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// The number of dominant colours to extract (1 to 64).
+    #[arg(long = "count", default_value_t = 5, value_parser = clap::value_parser!(u16).range(1..=64))]
+    pub count: u16,
+
+    /// The algorithm used to group similar colours together.
+    #[arg(long = "algorithm", default_value = "k-means")]
+    pub algorithm: QuantizeAlgorithm,
+
+    /// How the extracted colours should be output.
+    #[arg(long = "format", default_value = "hex")]
+    pub format: PaletteOutputFormat,
+
+    /// A path on disk to where the swatch image should be placed.
+    /// Required when using the 'swatch' format.
+    #[arg(short = 'o', long = "output", required_if_eq("format", "swatch"))]
+    pub output_path: Option<PathBuf>,
+
+    /// Ignore pixels that are mostly transparent.
+    #[arg(long = "ignore-transparent", default_value_t = false)]
+    pub ignore_transparent: bool,
+
+    /// Ignore near-white pixels, such as product shot backgrounds.
+    #[arg(long = "ignore-white", default_value_t = false)]
+    pub ignore_white: bool,
+
+    /// The value (0 to 255) every channel must reach for a pixel to count as near-white.
+    #[arg(long = "white-threshold", default_value_t = 240)]
+    pub white_threshold: u8,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum PaletteOutputFormat {
+    /// Print one hex colour per line with its share of the image.
+    Hex,
+
+    /// Print a JSON document.
+    Json,
+
+    /// Render the colours as a swatch image.
+    Swatch,
+}
+
+#[derive(Debug, Serialize)]
+struct PaletteReport {
+    colors: Vec<PaletteColor>,
+    average: String,
+    accent: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PaletteColor {
+    hex: String,
+    rgb: [u8; 3],
+    percentage: f64,
+}
+
+const SWATCH_WIDTH: u32 = 800;
+const SWATCH_PALETTE_HEIGHT: u32 = 100;
+const SWATCH_SUMMARY_HEIGHT: u32 = 40;
+
+/// Accent colours are skipped when their HSL lightness falls outside of this range.
+const ACCENT_LIGHTNESS_RANGE: std::ops::RangeInclusive<f64> = 0.15..=0.85;
+
+impl ExecutableCommand for PaletteCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if let Some(output_path) = &self.output_path {
+            if output_path.exists() && !self.overwrite {
+                bail!(OUTPUT_ALREADY_EXISTS);
+            }
+        }
+
+        let image = ImageReader::open(&self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?
+            .into_rgba8();
+        let pixels = image
+            .pixels()
+            .filter(|p| !self.ignore_transparent || p[3] >= ALPHA_CUTOFF)
+            .map(|p| [p[0], p[1], p[2]])
+            .filter(|rgb| !self.ignore_white || rgb.iter().any(|c| *c < self.white_threshold));
+        let histogram = histogram(pixels);
+        if histogram.is_empty() {
+            bail!("no pixels were left to analyse after filtering");
+        }
+
+        // Assign every colour to its nearest palette entry to work out each entry's share.
+        let palette = self
+            .algorithm
+            .build_palette(&histogram, self.count as usize);
+        let mut counts = vec![0u64; palette.len()];
+        let mut sum = [0u64; 3];
+        for color in &histogram {
+            let nearest = (0..palette.len())
+                .min_by_key(|i| distance(palette[*i], color.rgb))
+                .unwrap_or(0);
+            counts[nearest] += color.count;
+            for (total, value) in sum.iter_mut().zip(color.rgb) {
+                *total += value as u64 * color.count;
+            }
+        }
+        let total: u64 = counts.iter().sum();
+        let mut colors: Vec<([u8; 3], f64)> = palette
+            .into_iter()
+            .zip(counts)
+            .filter(|(_, count)| *count > 0)
+            .map(|(rgb, count)| (rgb, count as f64 / total as f64 * 100.0))
+            .collect();
+        colors.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let average = sum.map(|s| ((s + total / 2) / total) as u8);
+        let accent = colors
+            .iter()
+            .filter(|(rgb, _)| ACCENT_LIGHTNESS_RANGE.contains(&lightness(*rgb)))
+            .max_by(|a, b| accent_score(a).total_cmp(&accent_score(b)))
+            .unwrap_or(&colors[0])
+            .0;
+
+        match self.format {
+            PaletteOutputFormat::Hex => {
+                for (rgb, percentage) in &colors {
+                    println!("{} {:.2}%", to_hex(*rgb), percentage);
+                }
+                println!("Average: {}", to_hex(average));
+                println!("Accent: {}", to_hex(accent));
+            }
+            PaletteOutputFormat::Json => {
+                let report = PaletteReport {
+                    colors: colors
+                        .iter()
+                        .map(|(rgb, percentage)| PaletteColor {
+                            hex: to_hex(*rgb),
+                            rgb: *rgb,
+                            percentage: (percentage * 100.0).round() / 100.0,
+                        })
+                        .collect(),
+                    average: to_hex(average),
+                    accent: to_hex(accent),
+                };
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            }
+            PaletteOutputFormat::Swatch => {
+                let output_path = self
+                    .output_path
+                    .context("an output path is required for swatch images")?;
+                let output_format =
+                    ImageFormat::from_path(&output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+                render_swatch(&colors, average, accent)
+                    .save_with_format(&output_path, output_format)
+                    .context(ERROR_IMGSAVE_CTX)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Draw the dominant colours as bars sized by their share, above the average and accent colours.
+fn render_swatch(colors: &[([u8; 3], f64)], average: [u8; 3], accent: [u8; 3]) -> RgbImage {
+    let mut edges = Vec::with_capacity(colors.len());
+    let mut cumulative = 0.0;
+    for (rgb, percentage) in colors {
+        cumulative += percentage;
+        edges.push((
+            (cumulative / 100.0 * SWATCH_WIDTH as f64).round() as u32,
+            *rgb,
+        ));
+    }
+
+    RgbImage::from_fn(
+        SWATCH_WIDTH,
+        SWATCH_PALETTE_HEIGHT + SWATCH_SUMMARY_HEIGHT,
+        |x, y| {
+            if y >= SWATCH_PALETTE_HEIGHT {
+                return Rgb(if x < SWATCH_WIDTH / 2 {
+                    average
+                } else {
+                    accent
+                });
+            }
+            Rgb(edges
+                .iter()
+                .find(|(edge, _)| x < *edge)
+                .or(edges.last())
+                .map(|(_, rgb)| *rgb)
+                .unwrap_or_default())
+        },
+    )
+}
+
+fn to_hex(rgb: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    (0..3)
+        .map(|c| (a[c] as i32 - b[c] as i32).pow(2) as u32)
+        .sum()
+}
+
+fn lightness(rgb: [u8; 3]) -> f64 {
+    let max = *rgb.iter().max().unwrap_or(&0) as f64 / 255.0;
+    let min = *rgb.iter().min().unwrap_or(&0) as f64 / 255.0;
+    (max + min) / 2.0
+}
+
+/// Favour vivid colours while still giving some weight to how much of the image they cover.
+fn accent_score((rgb, percentage): &([u8; 3], f64)) -> f64 {
+    let max = *rgb.iter().max().unwrap_or(&0) as f64 / 255.0;
+    let min = *rgb.iter().min().unwrap_or(&0) as f64 / 255.0;
+    let saturation = if max == min {
+        0.0
+    } else {
+        (max - min) / (1.0 - (2.0 * lightness(*rgb) - 1.0).abs())
+    };
+    saturation * percentage.sqrt()
+}
@@ -0,0 +1,304 @@
+use crate::commands::ExecutableCommand;
+use crate::commands::messages::{
+    ERROR_IMGDECODE_CTX, ERROR_IMGREAD_CTX, ERROR_IMGSAVE_CTX, ERROR_IMGTYPEPARSE_CTX,
+    INPUT_FILE_DOES_NOT_EXIST, INPUT_IS_NOT_FILE, OUTPUT_ALREADY_EXISTS,
+};
+use anyhow::{Context, Result, bail};
+use clap::{Parser, ValueEnum};
+use image::{ColorType, DynamicImage, ImageFormat, ImageReader, Rgb, RgbImage};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+/// Print per-channel statistics and an optional histogram of an image.
+///
+/// 8-bit images are measured on a 0-255 scale and all other images on a 0-65535
+/// scale, with 32-bit float images analysed at 16-bit precision.
+#[derive(Debug, Clone, Parser)]
+pub struct StatsCommand {
+    /// A path on disk to the image that should be loaded.
+    #[arg(short = 'i', long = "input")]
+    pub input_path: PathBuf,
+
+    /// A comma-separated list of percentiles (0 to 100) to report for each channel.
+    #[arg(
+        long = "percentiles",
+        value_delimiter = ',',
+        default_value = "1,5,50,95,99"
+    )]
+    pub percentiles: Vec<f64>,
+
+    /// Output a histogram of each channel.
+    #[arg(long = "histogram")]
+    pub histogram: Option<HistogramOutput>,
+
+    /// A path on disk to where the histogram image should be placed.
+    /// Required when using the 'png' histogram.
+    #[arg(short = 'o', long = "output", required_if_eq("histogram", "png"))]
+    pub output_path: Option<PathBuf>,
+
+    /// Overwrite any existing file at the output path.
+    #[arg(long = "overwrite", default_value_t = false)]
+    pub overwrite: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HistogramOutput {
+    /// Print a bar chart of each channel to the terminal.
+    Text,
+
+    /// Render every channel into a single image.
+    Png,
+}
+
+const TEXT_HISTOGRAM_BINS: usize = 32;
+const TEXT_HISTOGRAM_WIDTH: usize = 50;
+const IMAGE_HISTOGRAM_BINS: usize = 256;
+const IMAGE_HISTOGRAM_HEIGHT: u32 = 128;
+
+/// The statistics gathered for a single channel.
+struct ChannelStats {
+    name: &'static str,
+    histogram: Vec<u64>,
+    min: usize,
+    max: usize,
+    mean: f64,
+    stddev: f64,
+    entropy: f64,
+}
+
+impl ChannelStats {
+    fn from_histogram(name: &'static str, histogram: Vec<u64>) -> Self {
+        let total: u64 = histogram.iter().sum();
+        let min = histogram.iter().position(|c| *c > 0).unwrap_or(0);
+        let max = histogram.iter().rposition(|c| *c > 0).unwrap_or(0);
+        let mean = histogram
+            .iter()
+            .enumerate()
+            .map(|(value, count)| value as f64 * *count as f64)
+            .sum::<f64>()
+            / total as f64;
+        let variance = histogram
+            .iter()
+            .enumerate()
+            .map(|(value, count)| (value as f64 - mean).powi(2) * *count as f64)
+            .sum::<f64>()
+            / total as f64;
+        let entropy = -histogram
+            .iter()
+            .filter(|count| **count > 0)
+            .map(|count| {
+                let p = *count as f64 / total as f64;
+                p * p.log2()
+            })
+            .sum::<f64>();
+
+        Self {
+            name,
+            histogram,
+            min,
+            max,
+            mean,
+            stddev: variance.sqrt(),
+            entropy,
+        }
+    }
+
+    /// Find the smallest value that at least `percentile` percent of samples are at or below.
+    fn percentile(&self, percentile: f64) -> usize {
+        let total: u64 = self.histogram.iter().sum();
+        let target = ((percentile / 100.0) * total as f64).ceil().max(1.0) as u64;
+        let mut cumulative = 0;
+        self.histogram
+            .iter()
+            .position(|count| {
+                cumulative += count;
+                cumulative >= target
+            })
+            .unwrap_or(self.max)
+    }
+
+    /// Merge the histogram into a smaller number of equally sized bins.
+    fn binned(&self, bins: usize) -> Vec<u64> {
+        let per_bin = self.histogram.len().div_ceil(bins);
+        self.histogram
+            .chunks(per_bin)
+            .map(|chunk| chunk.iter().sum())
+            .collect()
+    }
+}
+
+impl ExecutableCommand for StatsCommand {
+    fn run(self) -> Result<()> {
+        if !self.input_path.exists() {
+            bail!(INPUT_FILE_DOES_NOT_EXIST);
+        }
+        if !self.input_path.is_file() {
+            bail!(INPUT_IS_NOT_FILE);
+        }
+        if let Some(output_path) = &self.output_path {
+            if output_path.exists() && !self.overwrite {
+                bail!(OUTPUT_ALREADY_EXISTS);
+            }
+        }
+        if self.percentiles.iter().any(|p| !(0.0..=100.0).contains(p)) {
+            bail!("percentiles must be between 0 and 100");
+        }
+
+        let image = ImageReader::open(&self.input_path)
+            .context(ERROR_IMGREAD_CTX)?
+            .decode()
+            .context(ERROR_IMGDECODE_CTX)?;
+        let color = image.color();
+        let names: &[&'static str] = match color.channel_count() {
+            1 => &["Luma"],
+            2 => &["Luma", "Alpha"],
+            3 => &["Red", "Green", "Blue"],
+            _ => &["Red", "Green", "Blue", "Alpha"],
+        };
+
+        // Gather every sample at its native integer depth.
+        let (samples, levels): (Vec<u16>, usize) = match color {
+            ColorType::L8 | ColorType::La8 | ColorType::Rgb8 | ColorType::Rgba8 => (
+                image.as_bytes().iter().map(|v| *v as u16).collect(),
+                u8::MAX as usize + 1,
+            ),
+            _ => (sixteen_bit_samples(image), u16::MAX as usize + 1),
+        };
+        let channel_count = names.len();
+        let pixel_count = samples.len() / channel_count;
+        if pixel_count == 0 {
+            bail!("the image does not contain any pixels");
+        }
+
+        let mut histograms = vec![vec![0u64; levels]; channel_count];
+        let mut unique_colors = HashSet::new();
+        for pixel in samples.chunks_exact(channel_count) {
+            let mut packed = 0u64;
+            for (channel, value) in pixel.iter().enumerate() {
+                histograms[channel][*value as usize] += 1;
+                packed = (packed << 16) | *value as u64;
+            }
+            unique_colors.insert(packed);
+        }
+        let channels: Vec<ChannelStats> = names
+            .iter()
+            .zip(histograms)
+            .map(|(name, histogram)| ChannelStats::from_histogram(name, histogram))
+            .collect();
+
+        println!(
+            "{}",
+            self.input_path
+                .file_name()
+                .context("failed to obtain filename")?
+                .to_string_lossy()
+        );
+        println!("  * Color Type: {:?}", color);
+        println!("  * Pixels: {}", pixel_count);
+        println!("  * Unique Colors: {}", unique_colors.len());
+        for channel in &channels {
+            println!(
+                "  * {}: min {}, max {}, mean {:.2}, stddev {:.2}, entropy {:.3} bits",
+                channel.name,
+                channel.min,
+                channel.max,
+                channel.mean,
+                channel.stddev,
+                channel.entropy
+            );
+            if !self.percentiles.is_empty() {
+                let percentiles: Vec<String> = self
+                    .percentiles
+                    .iter()
+                    .map(|p| format!("p{} {}", p, channel.percentile(*p)))
+                    .collect();
+                println!("      Percentiles: {}", percentiles.join(", "));
+            }
+        }
+
+        match self.histogram {
+            Some(HistogramOutput::Text) => print_text_histogram(&channels, levels),
+            Some(HistogramOutput::Png) => {
+                let output_path = self
+                    .output_path
+                    .context("an output path is required for histogram images")?;
+                let output_format =
+                    ImageFormat::from_path(&output_path).context(ERROR_IMGTYPEPARSE_CTX)?;
+                render_histogram(&channels)
+                    .save_with_format(&output_path, output_format)
+                    .context(ERROR_IMGSAVE_CTX)?;
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+}
+
+fn sixteen_bit_samples(image: DynamicImage) -> Vec<u16> {
+    match image.color().channel_count() {
+        1 => image.into_luma16().into_raw(),
+        2 => image.into_luma_alpha16().into_raw(),
+        3 => image.into_rgb16().into_raw(),
+        _ => image.into_rgba16().into_raw(),
+    }
+}
+
+fn print_text_histogram(channels: &[ChannelStats], levels: usize) {
+    let bin_width = levels / TEXT_HISTOGRAM_BINS;
+    for channel in channels {
+        let bins = channel.binned(TEXT_HISTOGRAM_BINS);
+        let largest = *bins.iter().max().unwrap_or(&1).max(&1);
+        println!();
+        println!("{} histogram:", channel.name);
+        for (i, count) in bins.iter().enumerate() {
+            let length = (*count as f64 / largest as f64 * TEXT_HISTOGRAM_WIDTH as f64).round();
+            println!(
+                "  {:>5}-{:<5} {}",
+                i * bin_width,
+                (i + 1) * bin_width - 1,
+                "█".repeat(length as usize)
+            );
+        }
+    }
+}
+
+/// Draw each channel's histogram on top of one another, combining channel colours where they overlap.
+fn render_histogram(channels: &[ChannelStats]) -> RgbImage {
+    let binned: Vec<(Vec<u64>, [u8; 3])> = channels
+        .iter()
+        .map(|channel| {
+            let color = match channel.name {
+                "Red" => [255, 0, 0],
+                "Green" => [0, 255, 0],
+                "Blue" => [0, 0, 255],
+                "Alpha" => [96, 96, 96],
+                _ => [255, 255, 255],
+            };
+            (channel.binned(IMAGE_HISTOGRAM_BINS), color)
+        })
+        .collect();
+    let largest = binned
+        .iter()
+        .flat_map(|(bins, _)| bins.iter().copied())
+        .max()
+        .unwrap_or(1)
+        .max(1);
+
+    RgbImage::from_fn(
+        IMAGE_HISTOGRAM_BINS as u32,
+        IMAGE_HISTOGRAM_HEIGHT,
+        |x, y| {
+            let level = (IMAGE_HISTOGRAM_HEIGHT - y) as f64 / IMAGE_HISTOGRAM_HEIGHT as f64;
+            let mut pixel = [0u8; 3];
+            for (bins, color) in &binned {
+                if bins[x as usize] as f64 / largest as f64 >= level {
+                    for c in 0..3 {
+                        pixel[c] = pixel[c].saturating_add(color[c]);
+                    }
+                }
+            }
+            Rgb(pixel)
+        },
+    )
+}
@@ -0,0 +1,264 @@
+use crate::commands::ExecutableCommand;
+use crate::commands::helpers::collect_files;
+use anyhow::{Context, Result};
+use clap::Parser;
+use image::error::ParameterErrorKind;
+use image::{ImageError, ImageFormat, ImageReader};
+use rayon::prelude::*;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Fully decode images to find corrupt, truncated, empty, oversized or unsupported files.
+///
+/// Exits with 0 when every file is valid. Otherwise the exit code reflects the most
+/// severe problem found: 3 unsupported, 4 zero-byte, 5 oversized, 6 truncated, 7 corrupt.
+/// Exit codes 1 and 2 are left for other errors and invalid arguments.
+#[derive(Debug, Clone, Parser)]
+pub struct ValidateCommand {
+    /// Paths on disk to images or directories of images that should be validated.
+    #[arg(required = true)]
+    pub paths: Vec<PathBuf>,
+
+    /// Validate the images in directories recursively.
+    #[arg(short = 'r', long = "recursive", default_value_t = false)]
+    pub recursive: bool,
+
+    /// The largest acceptable file size in bytes.
+    #[arg(long = "max-bytes")]
+    pub max_bytes: Option<u64>,
+
+    /// The largest acceptable width or height in pixels.
+    #[arg(long = "max-dimension")]
+    pub max_dimension: Option<u32>,
+
+    /// A directory on disk that invalid files should be moved into.
+    #[arg(long = "quarantine")]
+    pub quarantine_path: Option<PathBuf>,
+
+    /// Also print files that are valid.
+    #[arg(short = 'v', long = "verbose", default_value_t = false)]
+    pub verbose: bool,
+}
+
+/// The reason a file failed validation, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Problem {
+    Unsupported,
+    ZeroByte,
+    Oversized,
+    Truncated,
+    Corrupt,
+}
+
+impl Problem {
+    fn exit_code(self) -> i32 {
+        match self {
+            Self::Unsupported => 3,
+            Self::ZeroByte => 4,
+            Self::Oversized => 5,
+            Self::Truncated => 6,
+            Self::Corrupt => 7,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Unsupported => "UNSUPPORTED",
+            Self::ZeroByte => "ZERO-BYTE",
+            Self::Oversized => "OVERSIZED",
+            Self::Truncated => "TRUNCATED",
+            Self::Corrupt => "CORRUPT",
+        }
+    }
+}
+
+/// The outcome of validating a single file.
+type Validation = Result<(), (Problem, String)>;
+
+/// How far from the end of a file to look for an end-of-image marker, since some
+/// encoders pad files after the marker.
+const TRAILER_SEARCH_BYTES: u64 = 1024;
+
+impl ValidateCommand {
+    fn validate(&self, path: &Path) -> Validation {
+        let bytes = path
+            .metadata()
+            .map_err(|err| (Problem::Corrupt, err.to_string()))?
+            .len();
+        if bytes == 0 {
+            return Err((Problem::ZeroByte, "file is empty".to_string()));
+        }
+        if let Some(max_bytes) = self.max_bytes.filter(|max| bytes > *max) {
+            return Err((
+                Problem::Oversized,
+                format!("file is {} bytes, the limit is {}", bytes, max_bytes),
+            ));
+        }
+
+        let open = || {
+            ImageReader::open(path)
+                .and_then(|reader| reader.with_guessed_format())
+                .map_err(|err| classify(&ImageError::IoError(err)))
+        };
+        let reader = open()?;
+        let Some(format) = reader.format() else {
+            return Err((
+                Problem::Unsupported,
+                "file is not in a recognised image format".to_string(),
+            ));
+        };
+
+        // Check the header before decoding so oversized images are never allocated.
+        if let Some(max_dimension) = self.max_dimension {
+            let (width, height) = reader.into_dimensions().map_err(|err| classify(&err))?;
+            if width > max_dimension || height > max_dimension {
+                return Err((
+                    Problem::Oversized,
+                    format!(
+                        "image is {}x{}, the limit is {} pixels per side",
+                        width, height, max_dimension
+                    ),
+                ));
+            }
+            open()?.decode().map_err(|err| classify(&err))?;
+        } else {
+            reader.decode().map_err(|err| classify(&err))?;
+        }
+
+        // Some decoders fill in missing data rather than failing, so check that
+        // formats with an end-of-image marker actually end with one. Some encoders pad
+        // JPEG files with zeros after the marker, so those are allowed.
+        let (trailer, padding): (&[u8], bool) = match format {
+            ImageFormat::Jpeg => (&[0xFF, 0xD9], true),
+            ImageFormat::Gif => (&[0x3B], false),
+            _ => return Ok(()),
+        };
+        let tail = read_tail(path, bytes).map_err(|err| (Problem::Corrupt, err.to_string()))?;
+        let end = if padding {
+            tail.iter()
+                .rposition(|&byte| byte != 0)
+                .map_or(0, |i| i + 1)
+        } else {
+            tail.len()
+        };
+        if !tail[..end].ends_with(trailer) {
+            return Err((
+                Problem::Truncated,
+                "file is missing its end-of-image marker".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn read_tail(path: &Path, length: u64) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let start = length.saturating_sub(TRAILER_SEARCH_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail)?;
+    Ok(tail)
+}
+
+/// Work out whether a decoding error was caused by missing data or by invalid data.
+fn classify(err: &ImageError) -> (Problem, String) {
+    let problem = match err {
+        ImageError::Unsupported(_) => Problem::Unsupported,
+        ImageError::Limits(_) => Problem::Oversized,
+        ImageError::IoError(io_err) if io_err.kind() == io::ErrorKind::UnexpectedEof => {
+            Problem::Truncated
+        }
+        ImageError::Parameter(parameter)
+            if matches!(parameter.kind(), ParameterErrorKind::NoMoreData) =>
+        {
+            Problem::Truncated
+        }
+        ImageError::Decoding(decoding) if decoding.source().is_some_and(unexpected_eof) => {
+            Problem::Truncated
+        }
+        _ => Problem::Corrupt,
+    };
+    (problem, err.to_string())
+}
+
+/// Whether an error was caused by reaching the end of the file, which some decoders
+/// report by wrapping the underlying I/O error in their own error type.
+fn unexpected_eof(err: &(dyn Error + 'static)) -> bool {
+    iter::successors(Some(err), |&err| err.source()).any(|err| {
+        err.downcast_ref::<io::Error>()
+            .is_some_and(|err| err.kind() == io::ErrorKind::UnexpectedEof)
+    })
+}
+
+/// Move a file into the quarantine directory, picking a free name if one is already taken.
+fn quarantine(path: &Path, directory: &Path) -> io::Result<PathBuf> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut destination = directory.join(file_name.as_ref());
+    let mut attempt = 1;
+    while destination.exists() {
+        destination = directory.join(format!("{}.{}", file_name, attempt));
+        attempt += 1;
+    }
+    if fs::rename(path, &destination).is_err() {
+        // Renaming fails across filesystems, so fall back to copying.
+        fs::copy(path, &destination)?;
+        fs::remove_file(path)?;
+    }
+    Ok(destination)
+}
+
+impl ExecutableCommand for ValidateCommand {
+    fn run(self) -> Result<()> {
+        let files = collect_files(&self.paths, self.recursive)?;
+        if let Some(directory) = &self.quarantine_path {
+            fs::create_dir_all(directory).context("failed to create quarantine directory")?;
+        }
+
+        let results: Vec<(PathBuf, Validation)> = files
+            .into_par_iter()
+            .map(|path| {
+                let result = self.validate(&path);
+                (path, result)
+            })
+            .collect();
+
+        let mut worst: Option<Problem> = None;
+        let mut invalid = 0;
+        for (path, result) in &results {
+            match result {
+                Ok(()) => {
+                    if self.verbose {
+                        println!("{}: OK", path.display());
+                    }
+                }
+                Err((problem, reason)) => {
+                    invalid += 1;
+                    worst = worst.max(Some(*problem));
+                    println!("{}: {} ({})", path.display(), problem.label(), reason);
+                    if let Some(directory) = &self.quarantine_path {
+                        let destination = quarantine(path, directory)
+                            .with_context(|| format!("failed to quarantine {}", path.display()))?;
+                        println!("  moved to {}", destination.display());
+                    }
+                }
+            }
+        }
+
+        println!(
+            "Checked {} files: {} valid, {} invalid",
+            results.len(),
+            results.len() - invalid,
+            invalid
+        );
+        if let Some(problem) = worst {
+            process::exit(problem.exit_code());
+        }
+
+        Ok(())
+    }
+}